@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+
+use crate::{
+    colors,
+    grid::{shift_grid, Grid},
+    Board, BoardId, BoardShift, GameState, Points, Position, ShiftEvent,
+};
+
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AiPlayer>()
+            .add_systems((toggle_ai, ai_move, show_hint).in_set(OnUpdate(GameState::Playing)))
+            .add_system(fade_hint)
+            .add_system(stop_ai.in_schedule(OnEnter(GameState::GameOver)));
+    }
+}
+
+/// Time between two moves of the AI.
+const AI_INTERVAL: f32 = 0.15;
+
+#[derive(Resource)]
+pub struct AiPlayer {
+    enabled: bool,
+    timer: Timer,
+}
+
+impl Default for AiPlayer {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timer: Timer::from_seconds(AI_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+const DIRECTIONS: [BoardShift; 4] = [
+    BoardShift::Up,
+    BoardShift::Left,
+    BoardShift::Right,
+    BoardShift::Down,
+];
+
+/// Scores a board: empty cells, rows and columns ordered by value, and equal
+/// neighbours that can merge next move are all good.
+fn evaluate(grid: &Grid) -> f32 {
+    let rank = |x: u8, y: u8| grid.get(x, y).map_or(0.0, |value| (value as f32).log2());
+
+    let mut monotonicity = 0.0;
+    let mut merges = 0.0;
+
+    // penalises a line for going both up and down, and counts equal neighbours
+    let mut score_line = |cells: Vec<(u8, u8)>| {
+        let (mut up, mut down) = (0.0, 0.0);
+
+        for pair in cells.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            let delta = rank(x2, y2) - rank(x1, y1);
+
+            if delta > 0.0 {
+                up += delta;
+            } else {
+                down -= delta;
+            }
+
+            if grid.get(x2, y2).is_some() && grid.get(x2, y2) == grid.get(x1, y1) {
+                merges += 1.0;
+            }
+        }
+
+        monotonicity -= f32::min(up, down);
+    };
+
+    for y in 0..grid.height() {
+        score_line((0..grid.width()).map(|x| (x, y)).collect());
+    }
+    for x in 0..grid.width() {
+        score_line((0..grid.height()).map(|y| (x, y)).collect());
+    }
+
+    grid.empty_cells() as f32 * 2.7 + monotonicity + merges * 1.5
+}
+
+/// The move leading to the best scoring board, `None` if nothing can move.
+pub fn best_move(grid: &Grid) -> Option<BoardShift> {
+    DIRECTIONS
+        .into_iter()
+        .filter_map(|dir| {
+            let (next, _, moved) = shift_grid(grid, dir);
+            moved.then(|| (dir, evaluate(&next)))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(dir, _)| dir)
+}
+
+fn toggle_ai(input: Res<Input<KeyCode>>, mut ai: ResMut<AiPlayer>) {
+    if input.just_pressed(KeyCode::Space) {
+        ai.enabled = !ai.enabled;
+        ai.timer.reset();
+    }
+}
+
+fn ai_move(
+    time: Res<Time>,
+    mut ai: ResMut<AiPlayer>,
+    query_board: Query<&Board>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    mut shift_events: EventWriter<ShiftEvent>,
+) {
+    if !ai.enabled || !ai.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    // every board shifts together, so the AI plays the first one
+    let Some(board) = query_board.iter().min_by_key(|board| board.id.0) else {
+        return;
+    };
+
+    if let Some(dir) = best_move(&board.grid(tiles.iter())) {
+        shift_events.send(ShiftEvent(dir));
+    }
+}
+
+fn stop_ai(mut ai: ResMut<AiPlayer>) {
+    ai.enabled = false;
+}
+
+/// Board edge lit up to suggest a move.
+#[derive(Component)]
+struct Hint {
+    timer: Timer,
+}
+
+const HINT_THICKNESS: f32 = 8.0;
+
+fn show_hint(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    query_board: Query<(Entity, &Board)>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    hints: Query<Entity, With<Hint>>,
+) {
+    if !input.just_pressed(KeyCode::H) {
+        return;
+    }
+
+    for entity in hints.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for (board_entity, board) in query_board.iter() {
+        if let Some(dir) = best_move(&board.grid(tiles.iter())) {
+            spawn_hint(&mut commands, board_entity, board, dir);
+        }
+    }
+}
+
+/// Hints are children of their board, so they sit on its edge wherever the
+/// board is.
+fn spawn_hint(commands: &mut Commands, board_entity: Entity, board: &Board, dir: BoardShift) {
+    let edge = board.physical_size / 2.0;
+    let (translation, size) = match dir {
+        BoardShift::Left => (
+            Vec2::new(-edge.x, 0.0),
+            Vec2::new(HINT_THICKNESS, board.physical_size.y),
+        ),
+        BoardShift::Right => (
+            Vec2::new(edge.x, 0.0),
+            Vec2::new(HINT_THICKNESS, board.physical_size.y),
+        ),
+        BoardShift::Up => (
+            Vec2::new(0.0, edge.y),
+            Vec2::new(board.physical_size.x, HINT_THICKNESS),
+        ),
+        BoardShift::Down => (
+            Vec2::new(0.0, -edge.y),
+            Vec2::new(board.physical_size.x, HINT_THICKNESS),
+        ),
+    };
+
+    let hint = commands
+        .spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: colors::HINT,
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation.extend(3.0)),
+                ..default()
+            },
+            Hint {
+                timer: Timer::from_seconds(1.0, TimerMode::Once),
+            },
+        ))
+        .id();
+    commands.entity(board_entity).add_child(hint);
+}
+
+fn fade_hint(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hints: Query<(Entity, &mut Hint, &mut Sprite)>,
+) {
+    for (entity, mut hint, mut sprite) in hints.iter_mut() {
+        hint.timer.tick(time.delta());
+
+        if hint.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            sprite.color.set_a(hint.timer.percent_left());
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::{replay::ReplayRecorder, Game, GameState, MergeEvent};
+
+/// Plain text summaries of every move on the `announcements` log target,
+/// for screen readers and other tools following the log.
+pub struct AnnouncementsPlugin;
+
+impl Plugin for AnnouncementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Announcements>()
+            .add_system(toggle_announcements)
+            .add_system(
+                announce_moves
+                    .after(crate::board_shift)
+                    .after(crate::undo)
+                    .in_set(OnUpdate(GameState::Playing)),
+            );
+    }
+}
+
+/// Whether moves are announced, off by default and toggled with F8.
+#[derive(Default, Resource)]
+pub struct Announcements(pub bool);
+
+fn toggle_announcements(input: Res<Input<KeyCode>>, mut announcements: ResMut<Announcements>) {
+    if input.just_pressed(KeyCode::F8) {
+        announcements.0 = !announcements.0;
+        info!(
+            target: "announcements",
+            "announcements {}",
+            if announcements.0 { "on" } else { "off" }
+        );
+    }
+}
+
+/// Watches the replay recording, which grows by one for every move that
+/// changed the board and shrinks on undo.
+fn announce_moves(
+    announcements: Res<Announcements>,
+    recorder: Res<ReplayRecorder>,
+    game: Res<Game>,
+    mut merge_events: EventReader<MergeEvent>,
+    mut recorded: Local<usize>,
+) {
+    let merges = merge_events
+        .iter()
+        .map(|event| {
+            format!(
+                "merged {0} and {0} into {1}",
+                event.points / 2,
+                event.points
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let moves = recorder.moves();
+    let previous = std::mem::replace(&mut *recorded, moves.len());
+    if !announcements.0 || moves.len() == previous {
+        return;
+    }
+
+    if moves.len() + 1 == previous {
+        info!(target: "announcements", "undone, score {}", game.score);
+        return;
+    }
+    // a reset clears the recording
+    let Some(last) = moves.last().filter(|_| moves.len() > previous) else {
+        info!(target: "announcements", "new game");
+        return;
+    };
+
+    let direction = format!("{:?}", last.direction).to_lowercase();
+    if merges.is_empty() {
+        info!(target: "announcements", "moved {direction}, score {}", game.score);
+    } else {
+        info!(
+            target: "announcements",
+            "moved {direction}, {}, score {}",
+            merges.join(", "),
+            game.score
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use bevy::{
+    asset::{HandleId, LoadState},
+    prelude::*,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{storage, GameOverEvent, GameState, MergeEvent, NewTileEvent};
+
+pub const AUDIO_SETTINGS_FILE: &str = "audio.json";
+const VOLUME_STEP: f32 = 0.1;
+
+pub struct GameAudioPlugin;
+
+impl Plugin for GameAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<AudioSettings>(AUDIO_SETTINGS_FILE))
+            .init_resource::<MusicConfig>()
+            .init_resource::<AudioAssets>()
+            .init_resource::<Music>()
+            .add_systems((
+                warn_missing_sounds,
+                adjust_audio_settings,
+                apply_audio_settings.after(adjust_audio_settings),
+                start_music,
+                play_merge_sound,
+                play_new_tile_sound,
+                play_game_over_sound,
+            ))
+            .add_system(pause_music.in_schedule(OnEnter(GameState::Paused)))
+            .add_system(resume_music.in_schedule(OnExit(GameState::Paused)));
+    }
+}
+
+/// Master volume shared by every sound, persisted between runs.
+#[derive(Debug, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub muted: bool,
+    pub volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            muted: false,
+            volume: 1.0,
+        }
+    }
+}
+
+impl AudioSettings {
+    /// Steps the volume up, wrapping around to silent past full volume.
+    pub fn next_volume(&mut self) {
+        self.volume = if self.volume >= 1.0 - VOLUME_STEP / 2.0 {
+            0.0
+        } else {
+            (self.volume + VOLUME_STEP).min(1.0)
+        };
+    }
+
+    fn effective_volume(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
+    }
+}
+
+/// Asset path of the background track, relative to the `assets` folder.
+/// Insert it before adding the plugin to play a different track.
+#[derive(Resource)]
+pub struct MusicConfig {
+    pub path: String,
+}
+
+impl Default for MusicConfig {
+    fn default() -> Self {
+        Self {
+            path: "sounds/music.wav".to_string(),
+        }
+    }
+}
+
+/// Sink of the looping background track, once it has started.
+#[derive(Resource, Default)]
+pub struct Music {
+    pub sink: Option<Handle<AudioSink>>,
+}
+
+#[derive(Resource)]
+pub struct AudioAssets {
+    merge: Handle<AudioSource>,
+    new_tile: Handle<AudioSource>,
+    game_over: Handle<AudioSource>,
+    music: Handle<AudioSource>,
+    warned: Vec<HandleId>,
+}
+
+impl FromWorld for AudioAssets {
+    fn from_world(world: &mut World) -> Self {
+        let music_path = world.resource::<MusicConfig>().path.clone();
+        let asset_server = world.resource::<AssetServer>();
+
+        Self {
+            merge: asset_server.load("sounds/merge.wav"),
+            new_tile: asset_server.load("sounds/new_tile.wav"),
+            game_over: asset_server.load("sounds/game_over.wav"),
+            music: asset_server.load(music_path),
+            warned: Vec::new(),
+        }
+    }
+}
+
+impl AudioAssets {
+    fn handles(&self) -> [&Handle<AudioSource>; 4] {
+        [&self.merge, &self.new_tile, &self.game_over, &self.music]
+    }
+}
+
+/// Plays `sound` if it has loaded and audio isn't muted. Bevy keeps queueing
+/// sounds that never load, so a missing file would otherwise pile up in the
+/// audio queue.
+fn play(
+    audio: &Audio,
+    sources: &Assets<AudioSource>,
+    audio_settings: &AudioSettings,
+    sound: &Handle<AudioSource>,
+    settings: PlaybackSettings,
+) {
+    if !audio_settings.muted && sources.contains(sound) {
+        audio.play_with_settings(
+            sound.clone(),
+            settings.with_volume(audio_settings.effective_volume()),
+        );
+    }
+}
+
+/// M toggles mute, minus and equals step the volume.
+fn adjust_audio_settings(input: Res<Input<KeyCode>>, mut settings: ResMut<AudioSettings>) {
+    if input.just_pressed(KeyCode::M) {
+        settings.muted = !settings.muted;
+    }
+
+    let step = if input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        -VOLUME_STEP
+    } else if input.any_just_pressed([KeyCode::Equals, KeyCode::NumpadAdd]) {
+        VOLUME_STEP
+    } else {
+        return;
+    };
+    settings.volume = (settings.volume + step).clamp(0.0, 1.0);
+}
+
+/// Pushes settings changes to the playing music and saves them.
+fn apply_audio_settings(
+    settings: Res<AudioSettings>,
+    music: Res<Music>,
+    sinks: Res<Assets<AudioSink>>,
+) {
+    if !settings.is_changed() || settings.is_added() {
+        return;
+    }
+
+    if let Some(sink) = music.sink.as_ref().and_then(|sink| sinks.get(sink)) {
+        sink.set_volume(settings.effective_volume());
+    }
+    storage::save(AUDIO_SETTINGS_FILE, &*settings);
+}
+
+fn warn_missing_sounds(mut assets: ResMut<AudioAssets>, asset_server: Res<AssetServer>) {
+    let failed = assets
+        .handles()
+        .into_iter()
+        .filter(|handle| asset_server.get_load_state(*handle) == LoadState::Failed)
+        .map(|handle| handle.id())
+        .filter(|id| !assets.warned.contains(id))
+        .collect::<Vec<_>>();
+
+    for id in failed {
+        let path = asset_server
+            .get_handle_path(id)
+            .map(|path| path.path().display().to_string())
+            .unwrap_or_default();
+        warn!("could not load sound {}, playing silence instead", path);
+        assets.warned.push(id);
+    }
+}
+
+/// Plays one merge sound per move, pitched up with the biggest merged value.
+fn play_merge_sound(
+    mut merge_events: EventReader<MergeEvent>,
+    audio: Res<Audio>,
+    sources: Res<Assets<AudioSource>>,
+    assets: Res<AudioAssets>,
+    settings: Res<AudioSettings>,
+) {
+    let Some(points) = merge_events.iter().map(|event| event.points).max() else {
+        return;
+    };
+
+    let speed = (1.0 + 0.04 * (points.ilog2() as f32 - 2.0)).clamp(1.0, 1.5);
+    play(
+        &audio,
+        &sources,
+        &settings,
+        &assets.merge,
+        PlaybackSettings::ONCE.with_speed(speed),
+    );
+}
+
+fn play_new_tile_sound(
+    mut tile_events: EventReader<NewTileEvent>,
+    audio: Res<Audio>,
+    sources: Res<Assets<AudioSource>>,
+    assets: Res<AudioAssets>,
+    settings: Res<AudioSettings>,
+) {
+    if tile_events.iter().count() > 0 {
+        play(
+            &audio,
+            &sources,
+            &settings,
+            &assets.new_tile,
+            PlaybackSettings::ONCE,
+        );
+    }
+}
+
+fn play_game_over_sound(
+    mut game_over_events: EventReader<GameOverEvent>,
+    audio: Res<Audio>,
+    sources: Res<Assets<AudioSource>>,
+    assets: Res<AudioAssets>,
+    settings: Res<AudioSettings>,
+) {
+    if game_over_events.iter().count() == 0 {
+        return;
+    }
+
+    play(
+        &audio,
+        &sources,
+        &settings,
+        &assets.game_over,
+        PlaybackSettings::ONCE,
+    );
+}
+
+/// Starts the background loop as soon as the track has loaded.
+fn start_music(
+    mut music: ResMut<Music>,
+    audio: Res<Audio>,
+    sources: Res<Assets<AudioSource>>,
+    sinks: Res<Assets<AudioSink>>,
+    assets: Res<AudioAssets>,
+    settings: Res<AudioSettings>,
+) {
+    if music.sink.is_some() || !sources.contains(&assets.music) {
+        return;
+    }
+
+    let sink = audio.play_with_settings(
+        assets.music.clone(),
+        PlaybackSettings::LOOP.with_volume(settings.effective_volume()),
+    );
+    music.sink = Some(sinks.get_handle(sink));
+}
+
+fn pause_music(music: Res<Music>, sinks: Res<Assets<AudioSink>>) {
+    if let Some(sink) = music.sink.as_ref().and_then(|sink| sinks.get(sink)) {
+        sink.pause();
+    }
+}
+
+fn resume_music(music: Res<Music>, sinks: Res<Assets<AudioSink>>) {
+    if let Some(sink) = music.sink.as_ref().and_then(|sink| sinks.get(sink)) {
+        sink.play();
+    }
+}
@@ -0,0 +1,258 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use clap::{builder::RangedU64ValueParser, error::ErrorKind, CommandFactory, Parser};
+
+use crate::{
+    savegame::AutosaveConfig, BoardConfig, SeedConfig, SpawnBias, StartConfig, ToastConfig,
+    UndoConfig,
+};
+
+/// Command line overrides of the game rules. Options left out keep the
+/// in-game defaults.
+#[derive(Debug, Parser, Resource)]
+#[command(about = "2048, the sliding tile game")]
+pub struct Args {
+    /// Size of the board, either `N` for a square or `WxH`, each between 2
+    /// and 16.
+    #[arg(long, value_name = "N|WxH", value_parser = parse_size)]
+    pub size: Option<(u8, u8)>,
+    /// Seed every game with this value instead of a random one.
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Tile value that wins the game, a power of two from 4 up.
+    #[arg(long)]
+    pub target: Option<u32>,
+    /// Number of tiles on the board when a game starts, at least 1.
+    #[arg(
+        long,
+        value_name = "TILES",
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..),
+    )]
+    pub start_tiles: Option<usize>,
+    /// Chance, between 0 and 1, for a new tile to be a 4 instead of a 2.
+    #[arg(long)]
+    pub four_chance: Option<f32>,
+    /// Keep the chance of a 4 fixed instead of raising it with the score.
+    #[arg(long)]
+    pub no_scaling: bool,
+    /// Where new tiles tend to appear.
+    #[arg(long, value_enum, default_value_t)]
+    pub spawn_bias: SpawnBias,
+    /// Undos allowed per game, a number or `unlimited`.
+    #[arg(long, value_name = "N|unlimited", value_parser = parse_undos)]
+    pub undos: Option<Undos>,
+    /// Save the game every this many moves, 0 to only save when closing.
+    #[arg(long, value_name = "MOVES", default_value_t = 0)]
+    pub autosave: u32,
+    /// How long, in milliseconds, "No moves!" shows before the game ends.
+    #[arg(long, value_name = "MS")]
+    pub toast_ms: Option<u64>,
+    /// Play this many games without a window and print their stats.
+    #[arg(long, value_name = "GAMES")]
+    pub headless: Option<u32>,
+    /// Make random moves in headless games instead of using the AI.
+    #[arg(long, requires = "headless")]
+    pub random: bool,
+    /// Play one game over stdin and stdout: the board is printed as a JSON
+    /// line after every move, and a direction is read back for the next one.
+    #[arg(long, conflicts_with = "headless")]
+    pub io: bool,
+}
+
+/// Longest board side `--size` takes, past it tiles get too small to read.
+const MAX_SIDE: u8 = 16;
+
+impl Args {
+    /// Parses the arguments, printing an error and exiting on invalid ones.
+    pub fn parse_valid() -> Self {
+        let args = Self::parse();
+
+        if let Err(message) = args.validate() {
+            Self::command()
+                .error(ErrorKind::ValueValidation, message)
+                .exit();
+        }
+        args
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some((width, height)) = self.size.filter(|(w, h)| *w < 2 || *h < 2) {
+            return Err(format!(
+                "--size must be at least 2 on each side, got {width}x{height}"
+            ));
+        }
+        if let Some((width, height)) = self.size.filter(|(w, h)| *w > MAX_SIDE || *h > MAX_SIDE) {
+            return Err(format!(
+                "--size must be at most {MAX_SIDE} on each side, got {width}x{height}"
+            ));
+        }
+        if let Some(target) = self.target.filter(|target| !target.is_power_of_two()) {
+            return Err(format!("--target must be a power of two, got {target}"));
+        }
+        if let Some(target) = self.target.filter(|target| *target < 4) {
+            return Err(format!("--target must be at least 4, got {target}"));
+        }
+        if let Some(chance) = self
+            .four_chance
+            .filter(|chance| !(0.0..=1.0).contains(chance))
+        {
+            return Err(format!(
+                "--four-chance must be between 0 and 1, got {chance}"
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn board_config(&self) -> BoardConfig {
+        self.size
+            .map_or_else(BoardConfig::default, |(width, height)| BoardConfig {
+                width,
+                height,
+            })
+    }
+
+    /// Chance of a 4 for games run without the `Game` resource: the one
+    /// passed on the command line, the default one with `--no-scaling`, or
+    /// `None` to scale it with the score.
+    pub fn fixed_four_chance(&self) -> Option<f32> {
+        self.four_chance.or(self.no_scaling.then_some(0.1))
+    }
+
+    /// Chance of a 4 and whether to scale it with the score instead, as
+    /// `Game` uses them in every game but replays. A chance picked on the
+    /// command line is kept for the whole game.
+    pub fn four_chance_rules(&self) -> (f32, bool) {
+        (
+            self.four_chance.unwrap_or(0.1),
+            self.four_chance.is_none() && !self.no_scaling,
+        )
+    }
+
+    pub fn start_config(&self) -> StartConfig {
+        self.start_tiles
+            .map_or_else(StartConfig::default, |starting_tiles| StartConfig {
+                starting_tiles,
+            })
+    }
+
+    pub fn seed_config(&self) -> SeedConfig {
+        SeedConfig { seed: self.seed }
+    }
+
+    pub fn autosave_config(&self) -> AutosaveConfig {
+        AutosaveConfig {
+            every: self.autosave,
+        }
+    }
+
+    pub fn toast_config(&self) -> ToastConfig {
+        self.toast_ms
+            .map_or_else(ToastConfig::default, |ms| ToastConfig {
+                no_moves: Duration::from_millis(ms),
+            })
+    }
+
+    pub fn undo_config(&self) -> UndoConfig {
+        self.undos
+            .map_or_else(UndoConfig::default, |Undos(per_game)| UndoConfig {
+                per_game,
+            })
+    }
+}
+
+/// Value of `--undos`, `None` for unlimited. A newtype since clap reads a
+/// bare `Option<Option<u32>>` as an optional flag value.
+#[derive(Clone, Copy, Debug)]
+pub struct Undos(Option<u32>);
+
+fn parse_undos(value: &str) -> Result<Undos, String> {
+    if value.eq_ignore_ascii_case("unlimited") {
+        return Ok(Undos(None));
+    }
+    value
+        .trim()
+        .parse()
+        .map(|undos| Undos(Some(undos)))
+        .map_err(|err| format!("invalid number of undos `{value}`: {err}"))
+}
+
+/// Parses `N` as an `N`x`N` board and `WxH` as a `W` wide, `H` high one.
+fn parse_size(value: &str) -> Result<(u8, u8), String> {
+    let parse = |side: &str| {
+        side.trim()
+            .parse::<u8>()
+            .map_err(|err| format!("invalid board size `{value}`: {err}"))
+    };
+
+    match value.split_once(['x', 'X']) {
+        Some((width, height)) => Ok((parse(width)?, parse(height)?)),
+        None => parse(value).map(|size| (size, size)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses and validates `args` the way `parse_valid` does, an `Err`
+    /// being what exits non-zero.
+    fn check(args: &[&str]) -> Result<Args, String> {
+        let args =
+            Args::try_parse_from(["b2048"].iter().chain(args)).map_err(|err| err.to_string())?;
+        args.validate().map(|()| args)
+    }
+
+    #[test]
+    fn sizes_parse_as_squares_or_width_by_height() {
+        assert_eq!(check(&["--size", "5"]).unwrap().size, Some((5, 5)));
+        assert_eq!(check(&["--size", "6x3"]).unwrap().size, Some((6, 3)));
+        assert_eq!(check(&["--size", "4X 7"]).unwrap().size, Some((4, 7)));
+        assert_eq!(check(&["--size", "2x16"]).unwrap().size, Some((2, 16)));
+    }
+
+    #[test]
+    fn bad_sizes_are_rejected() {
+        for size in [
+            "", "x", "4x", "-3", "4x4x4", "256", "1", "1x4", "17", "4x17", "255",
+        ] {
+            assert!(check(&["--size", size]).is_err(), "--size {size}");
+        }
+    }
+
+    #[test]
+    fn undos_take_a_number_or_unlimited() {
+        let per_game = |value| check(&["--undos", value]).unwrap().undo_config().per_game;
+        assert_eq!(per_game("3"), Some(3));
+        assert_eq!(per_game("0"), Some(0));
+        assert_eq!(per_game("Unlimited"), None);
+        for undos in ["-1", "many", ""] {
+            assert!(check(&["--undos", undos]).is_err(), "--undos {undos}");
+        }
+    }
+
+    #[test]
+    fn target_is_a_power_of_two_from_4() {
+        assert!(check(&["--target", "4"]).is_ok());
+        assert!(check(&["--target", "2048"]).is_ok());
+        for target in ["0", "1", "2", "3", "100"] {
+            assert!(check(&["--target", target]).is_err(), "--target {target}");
+        }
+    }
+
+    #[test]
+    fn four_chance_is_between_0_and_1() {
+        assert!(check(&["--four-chance", "0"]).is_ok());
+        assert!(check(&["--four-chance", "1"]).is_ok());
+        assert!(check(&["--four-chance", "-0.1"]).is_err());
+        assert!(check(&["--four-chance", "1.5"]).is_err());
+    }
+
+    #[test]
+    fn invalid_combinations_are_rejected() {
+        assert!(check(&["--random"]).is_err());
+        assert!(check(&["--headless", "3", "--random"]).is_ok());
+        assert!(check(&["--io", "--headless", "3"]).is_err());
+        assert!(check(&["--start-tiles", "0"]).is_err());
+    }
+}
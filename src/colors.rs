@@ -1,30 +1,205 @@
-use bevy::prelude::Color;
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::{Color, DetectChanges, Res, Resource},
+};
+use serde::{Deserialize, Serialize};
 
-pub const BOARD: Color = Color::Lcha {
-    lightness: 0.06,
-    chroma: 0.088,
-    hue: 281.0,
-    alpha: 1.0,
+/// Number of tile colors, one per power of two up to 2048.
+const TIERS: usize = 11;
+
+/// Colors that change with the active `Theme`.
+pub struct Palette {
+    pub clear: Color,
+    pub board: Color,
+    pub tile_placeholder: Color,
+    /// Tile colors for 2, 4, 8, … 2048.
+    tiles: [Color; TIERS],
+}
+
+const fn lcha(lightness: f32, chroma: f32, hue: f32) -> Color {
+    Color::Lcha {
+        lightness,
+        chroma,
+        hue,
+        alpha: 1.0,
+    }
+}
+
+const DARK: Palette = Palette {
+    clear: Color::rgb(0.122, 0.149, 0.220),
+    board: lcha(0.06, 0.088, 281.0),
+    tile_placeholder: lcha(0.55, 0.5, 315.0),
+    tiles: [
+        lcha(0.85, 0.5, 315.0),
+        lcha(0.8, 0.55, 330.0),
+        lcha(0.72, 0.65, 345.0),
+        lcha(0.68, 0.75, 0.0),
+        lcha(0.62, 0.85, 15.0),
+        lcha(0.56, 0.95, 30.0),
+        lcha(0.78, 0.7, 70.0),
+        lcha(0.8, 0.75, 80.0),
+        lcha(0.82, 0.8, 90.0),
+        lcha(0.84, 0.85, 100.0),
+        lcha(0.86, 0.9, 110.0),
+    ],
+};
+
+const LIGHT: Palette = Palette {
+    clear: Color::rgb(0.98, 0.97, 0.94),
+    board: lcha(0.68, 0.1, 70.0),
+    tile_placeholder: lcha(0.78, 0.08, 75.0),
+    tiles: [
+        lcha(0.93, 0.08, 80.0),
+        lcha(0.9, 0.15, 80.0),
+        lcha(0.75, 0.45, 60.0),
+        lcha(0.7, 0.55, 50.0),
+        lcha(0.65, 0.65, 40.0),
+        lcha(0.58, 0.75, 35.0),
+        lcha(0.82, 0.5, 90.0),
+        lcha(0.8, 0.55, 90.0),
+        lcha(0.78, 0.6, 90.0),
+        lcha(0.76, 0.65, 92.0),
+        lcha(0.74, 0.7, 95.0),
+    ],
 };
 
-pub const TILE_PLACEHOLDER: Color = Color::Lcha {
+/// Tiles for colorblind players, the same in both themes. Every step is
+/// darker than the one before, and the hue alternates between blue and
+/// orange so neighbouring values never look alike.
+const COLORBLIND_TILES: [Color; TIERS] = [
+    lcha(0.95, 0.2, 250.0),
+    lcha(0.88, 0.5, 60.0),
+    lcha(0.81, 0.4, 250.0),
+    lcha(0.74, 0.7, 60.0),
+    lcha(0.67, 0.55, 250.0),
+    lcha(0.6, 0.8, 60.0),
+    lcha(0.53, 0.65, 250.0),
+    lcha(0.46, 0.8, 60.0),
+    lcha(0.39, 0.7, 250.0),
+    lcha(0.32, 0.75, 60.0),
+    lcha(0.25, 0.7, 250.0),
+];
+
+/// Tiers of `COLORBLIND_TILES` light enough for black text.
+const COLORBLIND_DARK_TEXT_TIERS: usize = 6;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    #[default]
+    Dark,
+}
+
+impl Theme {
+    pub fn next(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::Light,
+        }
+    }
+
+    pub fn palette(self) -> &'static Palette {
+        match self {
+            Theme::Light => &LIGHT,
+            Theme::Dark => &DARK,
+        }
+    }
+}
+
+/// Swaps the tile colors for `COLORBLIND_TILES`, toggled with C.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource, Serialize, Deserialize)]
+pub struct ColorblindMode(pub bool);
+
+/// Index of `value` in a tile color table, values above 2048 share the last.
+fn tier(value: u32) -> usize {
+    (value.max(2).ilog2() as usize - 1).min(TIERS - 1)
+}
+
+pub fn tile_color(theme: Theme, colorblind: ColorblindMode, value: u32) -> Color {
+    if colorblind.0 {
+        COLORBLIND_TILES[tier(value)]
+    } else {
+        theme.palette().tiles[tier(value)]
+    }
+}
+
+/// Dark text on the light low tiles, white from 8 upwards. The colorblind
+/// tiles darken more slowly, so they switch later.
+pub fn tile_text_color(colorblind: ColorblindMode, value: u32) -> Color {
+    let dark_text_tiers = if colorblind.0 {
+        COLORBLIND_DARK_TEXT_TIERS
+    } else {
+        2
+    };
+
+    if tier(value) >= dark_text_tiers {
+        Color::WHITE
+    } else {
+        Color::BLACK
+    }
+}
+
+/// How much of the tile color a shadow keeps.
+const SHADOW_SHADE: f32 = 0.55;
+
+/// Everything the tile colors depend on, for systems that paint tiles.
+#[derive(SystemParam)]
+pub struct TileColors<'w> {
+    theme: Res<'w, Theme>,
+    colorblind: Res<'w, ColorblindMode>,
+}
+
+impl TileColors<'_> {
+    pub fn tile(&self, value: u32) -> Color {
+        tile_color(*self.theme, *self.colorblind, value)
+    }
+
+    /// The tile color darkened, for the drop shadow under it.
+    pub fn shadow(&self, value: u32) -> Color {
+        let [red, green, blue, _] = self.tile(value).as_rgba_f32();
+        Color::rgb(
+            red * SHADOW_SHADE,
+            green * SHADOW_SHADE,
+            blue * SHADOW_SHADE,
+        )
+    }
+
+    pub fn text(&self, value: u32) -> Color {
+        tile_text_color(*self.colorblind, value)
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.theme.is_changed() || self.colorblind.is_changed()
+    }
+}
+
+pub const SCORE_BOX: Color = Color::Lcha {
     lightness: 0.55,
     chroma: 0.5,
     hue: 315.0,
     alpha: 1.0,
 };
 
-pub const TILE: Color = Color::Lcha {
-    lightness: 0.85,
-    chroma: 0.5,
-    hue: 315.0,
+pub const OVERLAY: Color = Color::Rgba {
+    red: 0.12,
+    green: 0.15,
+    blue: 0.22,
+    alpha: 0.8,
+};
+
+pub const HINT: Color = Color::Lcha {
+    lightness: 0.9,
+    chroma: 0.6,
+    hue: 110.0,
     alpha: 1.0,
 };
 
-pub const SCORE_BOX: Color = Color::Lcha {
-    lightness: 0.55,
-    chroma: 0.5,
-    hue: 315.0,
+/// The star on a tile worth the win target.
+pub const VICTORY_BADGE: Color = Color::Lcha {
+    lightness: 0.85,
+    chroma: 0.8,
+    hue: 85.0,
     alpha: 1.0,
 };
 
@@ -0,0 +1,181 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use itertools::Itertools;
+
+use crate::{colors::Theme, Board, BoardId, FontSpec, Points, Position};
+
+/// Draws the boards as blocks of numbers instead of tile sprites, for
+/// windows too small for the sprites and for screen readers or
+/// screenshots. Toggled with F10.
+pub struct CompactPlugin;
+
+impl Plugin for CompactPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CompactView>()
+            .add_system(toggle_compact_view)
+            .add_system(spawn_compact_view.after(toggle_compact_view))
+            .add_system(update_compact_view.after(spawn_compact_view));
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct CompactView(pub bool);
+
+#[derive(Component)]
+struct CompactText;
+
+/// Share of the window the text may take up.
+const COMPACT_FILL: f32 = 0.8;
+/// Width of a digit and height of a line of FiraSans, relative to the font
+/// size, to scale the text to the window.
+const DIGIT_WIDTH: f32 = 0.55;
+const LINE_HEIGHT: f32 = 1.2;
+/// Figure spaces are as wide as a digit, so padding with them keeps the
+/// columns lined up.
+const FIGURE_SPACE: char = '\u{2007}';
+
+fn toggle_compact_view(input: Res<Input<KeyCode>>, mut view: ResMut<CompactView>) {
+    if input.just_pressed(KeyCode::F10) {
+        view.0 = !view.0;
+    }
+}
+
+fn spawn_compact_view(
+    mut commands: Commands,
+    view: Res<CompactView>,
+    texts: Query<Entity, With<CompactText>>,
+    font_spec: Res<FontSpec>,
+    theme: Res<Theme>,
+) {
+    // respawned on a theme change for the new board color behind the text
+    if !view.is_changed() && !theme.is_changed() {
+        return;
+    }
+
+    for entity in texts.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !view.0 {
+        return;
+    }
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .insert(CompactText)
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font_spec.family.clone(),
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Right)
+                .with_style(Style {
+                    padding: UiRect::all(Val::Px(12.0)),
+                    ..default()
+                })
+                .with_background_color(theme.palette().board),
+            );
+        });
+}
+
+/// Hides the board, tiles included, while the text is up, and rebuilds the
+/// text from the tiles.
+fn update_compact_view(
+    view: Res<CompactView>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut boards: Query<(&Board, &mut Visibility)>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    roots: Query<&Children, With<CompactText>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (_, mut visibility) in boards.iter_mut() {
+        let wanted = if view.0 {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        };
+        if *visibility != wanted {
+            *visibility = wanted;
+        }
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if boards.is_empty() {
+        return;
+    }
+
+    let digits = tiles
+        .iter()
+        .map(|(_, points, _)| points.value.to_string().len())
+        .max()
+        .unwrap_or(1);
+    let pad = |cell: String| {
+        let padding = digits - cell.chars().count();
+        std::iter::repeat_n(FIGURE_SPACE, padding)
+            .chain(cell.chars())
+            .collect::<String>()
+    };
+    // boards in id order, a blank line apart
+    let blocks = boards
+        .iter()
+        .map(|(board, _)| board)
+        .sorted_by_key(|board| board.id.0)
+        .map(|board| {
+            let grid = board.grid(tiles.iter());
+            let lines = grid
+                .rows()
+                .into_iter()
+                .map(|row| {
+                    row.into_iter()
+                        .map(|cell| pad(cell.map_or(".".to_string(), |value| value.to_string())))
+                        .collect::<Vec<_>>()
+                        .join(&FIGURE_SPACE.to_string().repeat(2))
+                })
+                .collect::<Vec<_>>();
+            (board.width, lines)
+        })
+        .collect::<Vec<_>>();
+
+    let width = blocks.iter().map(|(width, _)| *width).max().unwrap_or(1);
+    let line_width = (digits + 2) * width as usize - 2;
+    let lines = blocks
+        .into_iter()
+        .map(|(_, lines)| lines)
+        .collect::<Vec<_>>()
+        .join(&String::new());
+    let font_size = f32::min(
+        window.width() * COMPACT_FILL / (line_width as f32 * DIGIT_WIDTH),
+        window.height() * COMPACT_FILL / (lines.len() as f32 * LINE_HEIGHT),
+    );
+
+    let value = lines.join("\n");
+
+    for children in roots.iter() {
+        let mut texts = texts.iter_many_mut(children);
+        while let Some(mut text) = texts.fetch_next() {
+            // left alone when nothing changed, so the text isn't laid out
+            // again every frame
+            let section = &text.sections[0];
+            if section.value != value || section.style.font_size != font_size {
+                let section = &mut text.sections[0];
+                section.value = value.clone();
+                section.style.font_size = font_size;
+            }
+        }
+    }
+}
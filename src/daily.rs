@@ -0,0 +1,64 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+
+/// A UTC calendar day, used to seed the daily challenge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    pub fn today_utc() -> Self {
+        let days = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs() / 86_400)
+            .unwrap_or_default();
+
+        Self::from_days_since_epoch(days as i64)
+    }
+
+    /// UTC day of a Unix timestamp in seconds.
+    pub fn from_timestamp(secs: u64) -> Self {
+        Self::from_days_since_epoch((secs / 86_400) as i64)
+    }
+
+    /// Howard Hinnant's `civil_from_days`.
+    fn from_days_since_epoch(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + i64::from(month <= 2)) as i32;
+
+        Self { year, month, day }
+    }
+
+    /// The same for everyone on that day, e.g. 20240131.
+    pub fn seed(self) -> u64 {
+        self.year as u64 * 10_000 + u64::from(self.month) * 100 + u64::from(self.day)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// Day of the daily challenge being played, taken once by `game_reset` so
+/// a game crossing midnight keeps its date. `None` outside daily games.
+#[derive(Default, Resource)]
+pub struct DailyChallenge {
+    pub date: Option<Date>,
+}
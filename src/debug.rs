@@ -0,0 +1,194 @@
+use bevy::{prelude::*, utils::HashMap};
+
+#[cfg(debug_assertions)]
+use crate::Position;
+use crate::{colors::TileColors, Board, BoardId, FontSpec, Points};
+
+/// Debugging aids that never touch the game state.
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<Heatmap>()
+            .add_system(toggle_debug_overlay)
+            .add_system(update_debug_overlay.after(toggle_debug_overlay))
+            .add_system(toggle_heatmap)
+            // after every system that paints tiles, `apply_tile_flash` being
+            // the only other one in `PostUpdate`, so the heatmap colors win
+            // while it is on
+            .add_system(
+                paint_heatmap
+                    .in_base_set(CoreSet::PostUpdate)
+                    .after(crate::apply_tile_flash),
+            );
+
+        // once the commands of `Update` are applied, so tiles despawned by a
+        // merge are gone
+        #[cfg(debug_assertions)]
+        app.add_system(validate_tiles.in_base_set(CoreSet::PostUpdate));
+    }
+}
+
+/// Shows the `Position` of every cell, toggled with F3.
+#[derive(Default, Resource)]
+pub struct DebugOverlay(pub bool);
+
+/// `(x,y)` label in the corner of a cell.
+#[derive(Component)]
+struct CellLabel;
+
+fn toggle_debug_overlay(input: Res<Input<KeyCode>>, mut overlay: ResMut<DebugOverlay>) {
+    if input.just_pressed(KeyCode::F3) {
+        overlay.0 = !overlay.0;
+    }
+}
+
+/// Labels are children of the board, so a respawned board loses them and
+/// gets a fresh set.
+fn update_debug_overlay(
+    mut commands: Commands,
+    overlay: Res<DebugOverlay>,
+    boards: Query<(Entity, &Board)>,
+    new_boards: Query<(), Added<Board>>,
+    labels: Query<Entity, With<CellLabel>>,
+    font_spec: Res<FontSpec>,
+) {
+    if !overlay.is_changed() && new_boards.is_empty() {
+        return;
+    }
+
+    for entity in labels.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !overlay.0 {
+        return;
+    }
+
+    for (board_entity, board) in boards.iter() {
+        let corner = board.layout.tile_size / 2.0 - 14.0;
+
+        commands.entity(board_entity).with_children(|builder| {
+            for x in 0..board.width {
+                for y in 0..board.height {
+                    builder.spawn((
+                        Text2dBundle {
+                            text: Text::from_section(
+                                format!("({x},{y})"),
+                                TextStyle {
+                                    font: font_spec.family.clone(),
+                                    font_size: 14.0,
+                                    color: Color::WHITE,
+                                },
+                            )
+                            .with_alignment(TextAlignment::Center),
+                            transform: Transform::from_xyz(
+                                board.physical_x(x) - corner,
+                                board.physical_y(y) + corner,
+                                4.0,
+                            ),
+                            ..default()
+                        },
+                        CellLabel,
+                    ));
+                }
+            }
+        });
+    }
+}
+
+/// Tints the tiles from cold to hot by the log2 of their value, relative
+/// to the largest tile on the board, instead of the per-value colors.
+/// Toggled with V.
+#[derive(Default, Resource)]
+pub struct Heatmap(pub bool);
+
+/// Hues of the smallest and the largest tile.
+const HEATMAP_COLD_HUE: f32 = 240.0;
+const HEATMAP_HOT_HUE: f32 = 0.0;
+
+fn toggle_heatmap(input: Res<Input<KeyCode>>, mut heatmap: ResMut<Heatmap>) {
+    if input.just_pressed(KeyCode::V) {
+        heatmap.0 = !heatmap.0;
+    }
+}
+
+/// Repaints every tile while on, as a new largest tile shifts the colors of
+/// all the others on its board, and puts the normal colors back when turned off.
+fn paint_heatmap(
+    heatmap: Res<Heatmap>,
+    mut tiles: Query<(&Points, &BoardId, &mut Sprite)>,
+    tile_colors: TileColors,
+) {
+    if !heatmap.0 {
+        if heatmap.is_changed() {
+            for (points, _, mut sprite) in tiles.iter_mut() {
+                sprite.color = tile_colors.tile(points.value);
+            }
+        }
+        return;
+    }
+
+    let mut max_levels = HashMap::new();
+    for (points, board_id, _) in tiles.iter() {
+        let level = max_levels.entry(*board_id).or_insert(1);
+        *level = (*level).max(points.value.max(2).ilog2());
+    }
+
+    for (points, board_id, mut sprite) in tiles.iter_mut() {
+        let max_level = max_levels[board_id];
+        // 2 is level 1, the coldest
+        let level = points.value.max(2).ilog2();
+        let heat = if max_level > 1 {
+            (level - 1) as f32 / (max_level - 1) as f32
+        } else {
+            0.0
+        };
+        let color = Color::hsl(
+            HEATMAP_COLD_HUE + (HEATMAP_HOT_HUE - HEATMAP_COLD_HUE) * heat,
+            0.8,
+            0.5,
+        );
+
+        // read first, so unchanged tiles aren't marked as changed
+        if sprite.color != color {
+            sprite.color = color;
+        }
+    }
+}
+
+/// Panics as soon as two tiles share a cell or a tile sits off the board,
+/// to catch mistakes in the shift logic during development. Left out of
+/// release builds.
+#[cfg(debug_assertions)]
+fn validate_tiles(boards: Query<&Board>, tiles: Query<(Entity, &Position, &Points, &BoardId)>) {
+    let mut occupied = HashMap::new();
+
+    for (entity, pos, points, board_id) in tiles.iter() {
+        let Some(board) = boards.iter().find(|board| board.id == *board_id) else {
+            panic!(
+                "tile {entity:?} worth {} is on board {board_id:?}, which doesn't exist",
+                points.value
+            );
+        };
+        assert!(
+            pos.x < board.width && pos.y < board.height,
+            "tile {entity:?} worth {} is at ({},{}), off the {}x{} board",
+            points.value,
+            pos.x,
+            pos.y,
+            board.width,
+            board.height,
+        );
+
+        if let Some((other, other_value)) =
+            occupied.insert((*board_id, *pos), (entity, points.value))
+        {
+            panic!(
+                "tiles {other:?} worth {other_value} and {entity:?} worth {} are both at ({},{})",
+                points.value, pos.x, pos.y,
+            );
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use bevy::{
+    prelude::*,
+    window::{PrimaryWindow, WindowMode, WindowResized},
+};
+
+use crate::Board;
+
+/// Window handling. The camera and the board are both centered on the
+/// origin, so nothing here has to re-place tiles.
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraZoom>()
+            .add_system(toggle_fullscreen)
+            .add_system(fit_board_to_window)
+            .add_system(ease_camera_zoom.after(fit_board_to_window));
+    }
+}
+
+fn toggle_fullscreen(
+    input: Res<Input<KeyCode>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !input.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+
+    window.mode = match window.mode {
+        WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+        _ => WindowMode::Windowed,
+    };
+}
+
+/// Height of the header UI the board has to stay clear of.
+pub const HEADER_HEIGHT: f32 = 170.0;
+const MARGIN: f32 = 20.0;
+
+/// How long the camera takes to zoom to a new board.
+const ZOOM_DURATION: f32 = 0.3;
+
+/// Camera scale easing towards a board that was just spawned.
+#[derive(Default, Resource)]
+struct CameraZoom {
+    from: f32,
+    to: f32,
+    /// `None` while the camera isn't zooming.
+    timer: Option<Timer>,
+}
+
+/// Sets the camera scale, keeping the board centered below the header.
+fn set_zoom(projection: &mut OrthographicProjection, transform: &mut Transform, scale: f32) {
+    projection.scale = scale;
+    transform.translation.y = HEADER_HEIGHT / 2.0 * scale;
+}
+
+/// Zooms the camera out until every board fits below the header, and moves it
+/// so the board sits in the middle of the remaining space. Only the camera
+/// changes, so tile positions stay in the same world coordinates. A new board
+/// eases into view, while a resized window follows right away.
+fn fit_board_to_window(
+    mut resized_events: EventReader<WindowResized>,
+    new_boards: Query<(), Added<Board>>,
+    boards: Query<(&Board, Option<&Parent>)>,
+    parents: Query<&Transform, Without<Camera2d>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&mut OrthographicProjection, &mut Transform), With<Camera2d>>,
+    mut zoom: ResMut<CameraZoom>,
+) {
+    let resized = resized_events.iter().count() > 0;
+    if !resized && new_boards.is_empty() {
+        return;
+    }
+
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    if boards.is_empty() {
+        return;
+    }
+    // boards placed by a parent are off center, and the camera stays
+    // centered, so fit twice the farthest edge from the middle
+    let extent = boards
+        .iter()
+        .map(|(board, parent)| {
+            let offset = parent
+                .and_then(|parent| parents.get(parent.get()).ok())
+                .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+            offset.abs() + board.physical_size / 2.0
+        })
+        .fold(Vec2::ZERO, Vec2::max);
+    let size = 2.0 * extent;
+
+    let available_width = window.width() - 2.0 * MARGIN;
+    let available_height = window.height() - HEADER_HEIGHT - 2.0 * MARGIN;
+    if available_width <= 0.0 || available_height <= 0.0 {
+        return;
+    }
+
+    let scale = f32::max(size.x / available_width, size.y / available_height).max(1.0);
+
+    if resized {
+        zoom.timer = None;
+        for (mut projection, mut transform) in cameras.iter_mut() {
+            set_zoom(&mut projection, &mut transform, scale);
+        }
+        return;
+    }
+
+    let current = cameras
+        .iter()
+        .next()
+        .map_or(1.0, |(projection, _)| projection.scale);
+    *zoom = CameraZoom {
+        from: current,
+        to: scale,
+        timer: Some(Timer::from_seconds(ZOOM_DURATION, TimerMode::Once)),
+    };
+}
+
+fn ease_camera_zoom(
+    time: Res<Time>,
+    mut zoom: ResMut<CameraZoom>,
+    mut cameras: Query<(&mut OrthographicProjection, &mut Transform), With<Camera2d>>,
+) {
+    let Some(timer) = zoom.timer.as_mut() else {
+        return;
+    };
+
+    timer.tick(time.delta());
+    let t = timer.percent();
+    let finished = timer.finished();
+    // smoothstep, easing in and out
+    let eased = t * t * (3.0 - 2.0 * t);
+    let scale = zoom.from + (zoom.to - zoom.from) * eased;
+
+    for (mut projection, mut transform) in cameras.iter_mut() {
+        set_zoom(&mut projection, &mut transform, scale);
+    }
+    if finished {
+        zoom.timer = None;
+    }
+}
@@ -0,0 +1,123 @@
+use b2048::grid::plan_shift;
+use bevy::prelude::*;
+
+use crate::{colors, Board, BoardId, BoardShift, FontSpec, MoveHistory, Points, Position};
+
+/// Faint arrows along the board edges for new players: the directions that
+/// would move something stand out, the last move the most. Toggled with F1,
+/// unlike the one-off hint on H.
+pub struct EdgeArrowsPlugin;
+
+impl Plugin for EdgeArrowsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShowHints>()
+            .add_system(toggle_hints)
+            .add_system(spawn_edge_arrows.after(toggle_hints))
+            .add_system(light_edge_arrows.after(spawn_edge_arrows));
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct ShowHints(pub bool);
+
+#[derive(Component)]
+struct EdgeArrow(BoardShift);
+
+/// Gap between the board edge and the middle of an arrow.
+const ARROW_MARGIN: f32 = 20.0;
+const ARROW_FONT_SIZE: f32 = 32.0;
+const LAST_MOVE_ALPHA: f32 = 0.9;
+const POSSIBLE_ALPHA: f32 = 0.45;
+const BLOCKED_ALPHA: f32 = 0.1;
+
+fn toggle_hints(input: Res<Input<KeyCode>>, mut hints: ResMut<ShowHints>) {
+    if input.just_pressed(KeyCode::F1) {
+        hints.0 = !hints.0;
+    }
+}
+
+/// Children of the board like the debug labels, so a respawned board gets
+/// a fresh set.
+fn spawn_edge_arrows(
+    mut commands: Commands,
+    hints: Res<ShowHints>,
+    boards: Query<(Entity, &Board)>,
+    new_boards: Query<(), Added<Board>>,
+    arrows: Query<Entity, With<EdgeArrow>>,
+    font_spec: Res<FontSpec>,
+) {
+    if !hints.is_changed() && new_boards.is_empty() {
+        return;
+    }
+
+    for entity in arrows.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !hints.0 {
+        return;
+    }
+
+    for (board_entity, board) in boards.iter() {
+        let edge = board.physical_size / 2.0 + ARROW_MARGIN;
+
+        commands.entity(board_entity).with_children(|builder| {
+            for (direction, glyph, translation) in [
+                (BoardShift::Left, "←", Vec2::new(-edge.x, 0.0)),
+                (BoardShift::Right, "→", Vec2::new(edge.x, 0.0)),
+                (BoardShift::Up, "↑", Vec2::new(0.0, edge.y)),
+                (BoardShift::Down, "↓", Vec2::new(0.0, -edge.y)),
+            ] {
+                builder.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            glyph,
+                            TextStyle {
+                                font: font_spec.family.clone(),
+                                font_size: ARROW_FONT_SIZE,
+                                // mid-tone, to show on both themes
+                                color: colors::SCORE_BOX.with_a(BLOCKED_ALPHA),
+                            },
+                        )
+                        .with_alignment(TextAlignment::Center),
+                        transform: Transform::from_translation(translation.extend(1.0)),
+                        ..default()
+                    },
+                    EdgeArrow(direction),
+                ));
+            }
+        });
+    }
+}
+
+/// Each board lights its own arrows, children of it.
+fn light_edge_arrows(
+    mut arrows: Query<(&EdgeArrow, &Parent, &mut Text)>,
+    boards: Query<&Board>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    history: Res<MoveHistory>,
+) {
+    if arrows.is_empty() {
+        return;
+    }
+
+    let last_move = history.0.back().map(|(direction, _)| *direction);
+
+    for (arrow, parent, mut text) in arrows.iter_mut() {
+        let Ok(board) = boards.get(parent.get()) else {
+            continue;
+        };
+        let alpha = if last_move == Some(arrow.0) {
+            LAST_MOVE_ALPHA
+        } else if plan_shift(&board.grid(tiles.iter()), arrow.0).moved {
+            POSSIBLE_ALPHA
+        } else {
+            BLOCKED_ALPHA
+        };
+
+        // read first, so unchanged arrows aren't marked as changed
+        if text.sections[0].style.color.a() != alpha {
+            text.sections[0].style.color.set_a(alpha);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use bevy::{
+    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use crate::FontSpec;
+
+/// Frame rate overlay for performance tuning, toggled with F2. Kept apart
+/// from `DebugPlugin` so release builds can leave it out.
+pub struct FpsPlugin;
+
+impl Plugin for FpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .init_resource::<FpsOverlay>()
+            .add_system(toggle_fps_overlay)
+            .add_system(spawn_fps_overlay.after(toggle_fps_overlay))
+            .add_system(update_fps_text.after(spawn_fps_overlay));
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct FpsOverlay(pub bool);
+
+#[derive(Component)]
+struct FpsText;
+
+fn toggle_fps_overlay(input: Res<Input<KeyCode>>, mut overlay: ResMut<FpsOverlay>) {
+    if input.just_pressed(KeyCode::F2) {
+        overlay.0 = !overlay.0;
+    }
+}
+
+fn spawn_fps_overlay(
+    mut commands: Commands,
+    overlay: Res<FpsOverlay>,
+    texts: Query<Entity, With<FpsText>>,
+    font_spec: Res<FontSpec>,
+) {
+    if !overlay.is_changed() {
+        return;
+    }
+
+    for entity in texts.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if !overlay.0 {
+        return;
+    }
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_spec.family.clone(),
+                font_size: 16.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                left: Val::Px(10.0),
+                bottom: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        FpsText,
+    ));
+}
+
+fn update_fps_text(diagnostics: Res<Diagnostics>, mut texts: Query<&mut Text, With<FpsText>>) {
+    let smoothed = |id| {
+        diagnostics
+            .get(id)
+            .and_then(|diagnostic| diagnostic.smoothed())
+    };
+    let fps = smoothed(FrameTimeDiagnosticsPlugin::FPS);
+    let frame_time = smoothed(FrameTimeDiagnosticsPlugin::FRAME_TIME);
+
+    for mut text in texts.iter_mut() {
+        text.sections[0].value = match (fps, frame_time) {
+            (Some(fps), Some(frame_time)) => format!("{fps:.0} fps  {frame_time:.2} ms"),
+            _ => "-- fps".to_string(),
+        };
+    }
+}
@@ -0,0 +1,425 @@
+use crate::{BoardShift, Position};
+
+/// Tile values of a board, detached from the ECS so moves can be simulated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid {
+    width: u8,
+    height: u8,
+    cells: Vec<Option<u32>>,
+}
+
+impl Grid {
+    pub fn new(width: u8, height: u8) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![None; usize::from(width) * usize::from(height)],
+        }
+    }
+
+    pub fn from_tiles(
+        width: u8,
+        height: u8,
+        tiles: impl IntoIterator<Item = (Position, u32)>,
+    ) -> Self {
+        let mut grid = Self::new(width, height);
+        for (pos, value) in tiles {
+            grid.set(pos.x, pos.y, Some(value));
+        }
+        grid
+    }
+
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    pub fn get(&self, x: u8, y: u8) -> Option<u32> {
+        self.cells[self.index(x, y)]
+    }
+
+    pub fn set(&mut self, x: u8, y: u8, value: Option<u32>) {
+        let index = self.index(x, y);
+        self.cells[index] = value;
+    }
+
+    /// Sum of all tile values, which no move can change.
+    fn total(&self) -> u32 {
+        self.cells.iter().flatten().sum()
+    }
+
+    pub fn empty_cells(&self) -> usize {
+        self.cells.iter().filter(|cell| cell.is_none()).count()
+    }
+
+    /// Free cells, column by column.
+    pub fn empty_positions(&self) -> Vec<Position> {
+        (0..self.width)
+            .flat_map(|x| (0..self.height).map(move |y| Position { x, y }))
+            .filter(|pos| self.get(pos.x, pos.y).is_none())
+            .collect()
+    }
+
+    /// Value of the biggest tile, 0 on an empty grid.
+    pub fn max_value(&self) -> u32 {
+        self.cells.iter().flatten().copied().max().unwrap_or(0)
+    }
+
+    /// Whether a tile has reached `target`.
+    pub fn has_won(&self, target: u32) -> bool {
+        self.cells.iter().flatten().any(|value| *value >= target)
+    }
+
+    /// Cell values row by row, top row first, as the board is drawn.
+    pub fn rows(&self) -> Vec<Vec<Option<u32>>> {
+        (0..self.height)
+            .rev()
+            .map(|y| (0..self.width).map(|x| self.get(x, y)).collect())
+            .collect()
+    }
+
+    /// Whether every cell holds a tile.
+    pub fn is_full(&self) -> bool {
+        self.empty_cells() == 0
+    }
+
+    fn index(&self, x: u8, y: u8) -> usize {
+        usize::from(y) * usize::from(self.width) + usize::from(x)
+    }
+
+    /// Number of rows or columns moving independently in `dir`.
+    fn line_count(&self, dir: BoardShift) -> u8 {
+        match dir {
+            BoardShift::Left | BoardShift::Right => self.height,
+            BoardShift::Up | BoardShift::Down => self.width,
+        }
+    }
+
+    /// Cells of one row or column, starting at the wall tiles move towards.
+    /// Rows span the width and columns the height.
+    fn line(&self, dir: BoardShift, index: u8) -> Vec<(u8, u8)> {
+        match dir {
+            BoardShift::Left => (0..self.width).map(|x| (x, index)).collect(),
+            BoardShift::Right => (0..self.width).rev().map(|x| (x, index)).collect(),
+            BoardShift::Up => (0..self.height).rev().map(|y| (index, y)).collect(),
+            BoardShift::Down => (0..self.height).map(|y| (index, y)).collect(),
+        }
+    }
+}
+
+/// Where a tile of the shifted grid came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Slide {
+    pub from: (u8, u8),
+    pub to: (u8, u8),
+    /// The tile merged into this one, if any.
+    pub merged: Option<(u8, u8)>,
+    pub value: u32,
+}
+
+/// Outcome of a move, with enough detail to apply it to the tile entities.
+pub struct Shift {
+    pub grid: Grid,
+    pub score_gain: u32,
+    pub moved: bool,
+    pub slides: Vec<Slide>,
+}
+
+/// Shifts every line towards `dir`. Tiles are paired up from the wall, so
+/// `[2, 2, 2, 2]` becomes `[4, 4]` and `[2, 2, 4, 4]` becomes `[4, 8]`:
+/// a tile created by a merge never merges again in the same move.
+pub fn plan_shift(grid: &Grid, dir: BoardShift) -> Shift {
+    let mut result = Grid::new(grid.width, grid.height);
+    let mut score_gain = 0;
+    let mut slides = Vec::new();
+
+    for index in 0..grid.line_count(dir) {
+        let line = grid.line(dir, index);
+        let mut tiles = line
+            .iter()
+            .filter_map(|&cell| grid.get(cell.0, cell.1).map(|value| (cell, value)))
+            .peekable();
+        let mut col = 0;
+
+        while let Some((from, value)) = tiles.next() {
+            // each tile merges at most once per move
+            let merged = tiles
+                .next_if(|(_, next_value)| *next_value == value)
+                .map(|(cell, _)| cell);
+            let value = if merged.is_some() {
+                score_gain += value * 2;
+                value * 2
+            } else {
+                value
+            };
+
+            let to = line[col];
+            result.set(to.0, to.1, Some(value));
+            slides.push(Slide {
+                from,
+                to,
+                merged,
+                value,
+            });
+            col += 1;
+        }
+    }
+
+    debug_assert_eq!(grid.total(), result.total(), "shift changed the tile total");
+    debug_assert_eq!(
+        slides.len() + slides.iter().filter(|slide| slide.merged.is_some()).count(),
+        grid.cells.len() - grid.empty_cells(),
+        "every tile must slide or merge exactly once"
+    );
+
+    Shift {
+        moved: result != *grid,
+        grid: result,
+        score_gain,
+        slides,
+    }
+}
+
+/// Whether a move in any direction changes the board. The game is lost once
+/// this is false. Runs the real moves rather than looking for gaps and equal
+/// neighbours, so it can't disagree with what a key press would do.
+pub fn has_any_move(grid: &Grid) -> bool {
+    [
+        BoardShift::Left,
+        BoardShift::Right,
+        BoardShift::Up,
+        BoardShift::Down,
+    ]
+    .into_iter()
+    .any(|dir| plan_shift(grid, dir).moved)
+}
+
+/// Applies a move to a copy of `grid`, returning the new grid, the points
+/// it scored and whether anything moved.
+pub fn shift_grid(grid: &Grid, dir: BoardShift) -> (Grid, u32, bool) {
+    let shift = plan_shift(grid, dir);
+    (shift.grid, shift.score_gain, shift.moved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a grid from rows written top row first, as `Grid::rows`
+    /// returns them, 0 being an empty cell.
+    fn grid(rows: &[&[u32]]) -> Grid {
+        let height = rows.len() as u8;
+        let width = rows[0].len() as u8;
+        let mut grid = Grid::new(width, height);
+        for (row, values) in rows.iter().enumerate() {
+            let y = height - 1 - row as u8;
+            for (x, &value) in values.iter().enumerate() {
+                grid.set(x as u8, y, (value != 0).then_some(value));
+            }
+        }
+        grid
+    }
+
+    fn rows(grid: &Grid) -> Vec<Vec<u32>> {
+        grid.rows()
+            .into_iter()
+            .map(|row| row.into_iter().map(|cell| cell.unwrap_or(0)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn equal_neighbours_merge() {
+        let start = grid(&[&[2, 2, 0, 0], &[0, 0, 0, 0]]);
+        let (next, gain, moved) = shift_grid(&start, BoardShift::Left);
+
+        assert!(moved);
+        assert_eq!(gain, 4);
+        assert_eq!(rows(&next), [[4, 0, 0, 0], [0, 0, 0, 0]]);
+    }
+
+    #[test]
+    fn tiles_merge_across_gaps() {
+        let start = grid(&[&[2, 0, 0, 2]]);
+        let (next, gain, _) = shift_grid(&start, BoardShift::Right);
+
+        assert_eq!(gain, 4);
+        assert_eq!(rows(&next), [[0, 0, 0, 4]]);
+    }
+
+    #[test]
+    fn different_tiles_only_slide() {
+        let start = grid(&[&[0, 2, 0, 4]]);
+        let (next, gain, moved) = shift_grid(&start, BoardShift::Left);
+
+        assert!(moved);
+        assert_eq!(gain, 0);
+        assert_eq!(rows(&next), [[2, 4, 0, 0]]);
+    }
+
+    #[test]
+    fn move_into_a_packed_wall_changes_nothing() {
+        let start = grid(&[&[2, 4, 0, 0], &[8, 0, 0, 0]]);
+        let (next, gain, moved) = shift_grid(&start, BoardShift::Left);
+
+        assert!(!moved);
+        assert_eq!(gain, 0);
+        assert_eq!(next, start);
+    }
+
+    #[test]
+    fn empty_grid_never_moves() {
+        let start = Grid::new(4, 4);
+        for dir in DIRECTIONS {
+            assert!(!shift_grid(&start, dir).2);
+        }
+    }
+
+    #[test]
+    fn merged_tile_does_not_merge_again() {
+        let start = grid(&[&[2, 2, 4, 0]]);
+        let (next, gain, _) = shift_grid(&start, BoardShift::Left);
+
+        assert_eq!(gain, 4);
+        assert_eq!(rows(&next), [[4, 4, 0, 0]]);
+    }
+
+    #[test]
+    fn tiles_pair_up_from_the_wall() {
+        let start = grid(&[&[2, 2, 2, 0]]);
+
+        let (left, _, _) = shift_grid(&start, BoardShift::Left);
+        assert_eq!(rows(&left), [[4, 2, 0, 0]]);
+
+        let (right, _, _) = shift_grid(&start, BoardShift::Right);
+        assert_eq!(rows(&right), [[0, 0, 2, 4]]);
+    }
+
+    #[test]
+    fn edge_rows_and_columns_shift() {
+        let start = grid(&[&[2, 0, 0, 2], &[0, 0, 0, 0], &[0, 0, 0, 0], &[4, 0, 0, 4]]);
+
+        let (up, gain, _) = shift_grid(&start, BoardShift::Up);
+        assert_eq!(gain, 0);
+        assert_eq!(
+            rows(&up),
+            [[2, 0, 0, 2], [4, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0]]
+        );
+
+        let (right, gain, _) = shift_grid(&start, BoardShift::Right);
+        assert_eq!(gain, 12);
+        assert_eq!(
+            rows(&right),
+            [[0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 8]]
+        );
+    }
+
+    #[test]
+    fn non_square_boards_shift_along_their_own_lengths() {
+        let start = grid(&[&[2, 0, 0, 0, 2], &[0, 0, 0, 0, 0]]);
+
+        let (next, _, _) = shift_grid(&start, BoardShift::Right);
+        assert_eq!(rows(&next), [[0, 0, 0, 0, 4], [0, 0, 0, 0, 0]]);
+
+        let (next, _, _) = shift_grid(&start, BoardShift::Down);
+        assert_eq!(rows(&next), [[0, 0, 0, 0, 0], [2, 0, 0, 0, 2]]);
+    }
+
+    const DIRECTIONS: [BoardShift; 4] = [
+        BoardShift::Left,
+        BoardShift::Right,
+        BoardShift::Up,
+        BoardShift::Down,
+    ];
+
+    /// Lays `values` along the first line of a 4x4 grid for `dir`, starting
+    /// at the wall the tiles move towards.
+    fn grid_with_line(dir: BoardShift, values: &[u32]) -> Grid {
+        let mut grid = Grid::new(4, 4);
+        for (&(x, y), &value) in grid.line(dir, 0).iter().zip(values) {
+            grid.set(x, y, Some(value));
+        }
+        grid
+    }
+
+    /// Values along the first line for `dir`, wall first, 0 being empty.
+    fn line_values(grid: &Grid, dir: BoardShift) -> Vec<u32> {
+        grid.line(dir, 0)
+            .into_iter()
+            .map(|(x, y)| grid.get(x, y).unwrap_or(0))
+            .collect()
+    }
+
+    /// No cell may be the source of more than one slide or merge.
+    fn assert_single_merge_per_tile(shift: &Shift) {
+        let mut sources = shift
+            .slides
+            .iter()
+            .flat_map(|slide| std::iter::once(slide.from).chain(slide.merged))
+            .collect::<Vec<_>>();
+        let count = sources.len();
+        sources.sort_unstable();
+        sources.dedup();
+        assert_eq!(sources.len(), count, "a tile moved or merged twice");
+    }
+
+    #[test]
+    fn full_lines_pair_up_in_every_direction() {
+        let cases: [([u32; 4], [u32; 4], u32); 3] = [
+            ([2, 2, 2, 2], [4, 4, 0, 0], 8),
+            ([4, 4, 4, 4], [8, 8, 0, 0], 16),
+            ([2, 2, 4, 4], [4, 8, 0, 0], 12),
+        ];
+
+        for dir in DIRECTIONS {
+            for (line, expected, expected_gain) in cases {
+                let shift = plan_shift(&grid_with_line(dir, &line), dir);
+
+                assert_eq!(line_values(&shift.grid, dir), expected, "{line:?} {dir:?}");
+                assert_eq!(shift.score_gain, expected_gain, "{line:?} {dir:?}");
+                assert_eq!(
+                    shift
+                        .slides
+                        .iter()
+                        .filter(|slide| slide.merged.is_some())
+                        .count(),
+                    2,
+                    "{line:?} {dir:?}"
+                );
+                assert_single_merge_per_tile(&shift);
+            }
+        }
+    }
+
+    #[test]
+    fn full_board_without_merges_has_no_move() {
+        let stuck = grid(&[&[2, 4, 2, 4], &[4, 2, 4, 2], &[2, 4, 2, 4], &[4, 2, 4, 2]]);
+        assert!(stuck.is_full());
+        assert!(!has_any_move(&stuck));
+    }
+
+    #[test]
+    fn full_board_with_a_horizontal_pair_has_a_move() {
+        let board = grid(&[&[2, 4, 2, 4], &[4, 8, 8, 2], &[2, 4, 2, 4], &[4, 2, 4, 2]]);
+        assert!(has_any_move(&board));
+        assert!(shift_grid(&board, BoardShift::Left).2);
+        assert!(!shift_grid(&board, BoardShift::Up).2);
+    }
+
+    #[test]
+    fn full_board_with_a_vertical_pair_has_a_move() {
+        let board = grid(&[&[2, 4, 2, 4], &[4, 8, 4, 2], &[2, 8, 2, 4], &[4, 2, 4, 2]]);
+        assert!(has_any_move(&board));
+        assert!(shift_grid(&board, BoardShift::Up).2);
+        assert!(!shift_grid(&board, BoardShift::Left).2);
+    }
+
+    #[test]
+    fn board_with_an_empty_cell_has_a_move() {
+        let board = grid(&[&[2, 4, 2, 4], &[4, 2, 4, 2], &[2, 4, 0, 4], &[4, 2, 4, 2]]);
+        assert!(!board.is_full());
+        assert!(has_any_move(&board));
+    }
+}
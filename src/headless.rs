@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use b2048::{shift_grid, simulate_game, BoardShift, GameResult, Grid, SimulationConfig};
+use bevy::{app::AppExit, prelude::*};
+use rand::prelude::*;
+
+use crate::{ai, cli::Args, BoardConfig};
+
+/// Plays `games` games without a window and prints how they went. Only the
+/// library model is used, so nothing but `MinimalPlugins` is needed.
+pub fn run(args: &Args, games: u32) {
+    App::new()
+        .add_plugins(MinimalPlugins)
+        .insert_resource(Simulation {
+            games,
+            board: args.board_config(),
+            starting_tiles: args.start_config().starting_tiles,
+            seed: args.seed,
+            four_chance: args.fixed_four_chance(),
+            target: args.target.unwrap_or(2048),
+            policy: if args.random {
+                Policy::Random
+            } else {
+                Policy::Ai
+            },
+            results: Vec::new(),
+        })
+        .add_system(simulate_next_game)
+        .run();
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Policy {
+    Random,
+    /// `ai::best_move`, the same as the in-game autoplay.
+    Ai,
+}
+
+#[derive(Resource)]
+struct Simulation {
+    games: u32,
+    board: BoardConfig,
+    starting_tiles: usize,
+    /// First seed, each later game adds one. Random when `None`.
+    seed: Option<u64>,
+    /// Fixed chance of a 4, or `None` to scale it with the score like the
+    /// game does.
+    four_chance: Option<f32>,
+    target: u32,
+    policy: Policy,
+    results: Vec<GameResult>,
+}
+
+/// One game per update, then the report once all are done.
+fn simulate_next_game(mut simulation: ResMut<Simulation>, mut exit: EventWriter<AppExit>) {
+    let played = simulation.results.len() as u32;
+    if played == simulation.games {
+        report(&simulation);
+        exit.send(AppExit);
+        return;
+    }
+
+    let seed = simulation
+        .seed
+        .map_or_else(rand::random, |seed| seed.wrapping_add(u64::from(played)));
+    let result = play(&simulation, seed);
+    simulation.results.push(result);
+}
+
+/// Plays until no move is left.
+fn play(simulation: &Simulation, seed: u64) -> GameResult {
+    let config = SimulationConfig {
+        width: simulation.board.width,
+        height: simulation.board.height,
+        starting_tiles: simulation.starting_tiles,
+        four_chance: simulation.four_chance,
+    };
+
+    simulate_game(&config, seed, |grid, rng| {
+        pick_move(grid, simulation.policy, rng)
+    })
+}
+
+fn pick_move(grid: &Grid, policy: Policy, rng: &mut impl Rng) -> Option<BoardShift> {
+    match policy {
+        Policy::Ai => ai::best_move(grid),
+        Policy::Random => {
+            let mut directions = [
+                BoardShift::Left,
+                BoardShift::Right,
+                BoardShift::Up,
+                BoardShift::Down,
+            ];
+            directions.shuffle(rng);
+            directions.into_iter().find(|dir| shift_grid(grid, *dir).2)
+        }
+    }
+}
+
+fn report(simulation: &Simulation) {
+    let results = &simulation.results;
+    let games = results.len().max(1) as f64;
+    let total_score: u64 = results.iter().map(|result| u64::from(result.score)).sum();
+    let total_moves: u64 = results.iter().map(|result| u64::from(result.moves)).sum();
+    let best = results.iter().map(|result| result.score).max().unwrap_or(0);
+    let wins = results
+        .iter()
+        .filter(|result| result.max_tile >= simulation.target)
+        .count();
+
+    let mut max_tiles = BTreeMap::<u32, u32>::new();
+    for result in results {
+        *max_tiles.entry(result.max_tile).or_default() += 1;
+    }
+
+    println!(
+        "{} games on a {}x{} board, {:?} moves",
+        results.len(),
+        simulation.board.width,
+        simulation.board.height,
+        simulation.policy
+    );
+    println!("average score: {:.1}", total_score as f64 / games);
+    println!("best score: {best}");
+    println!("average moves: {:.1}", total_moves as f64 / games);
+    println!(
+        "reached {}: {wins} ({:.1}%)",
+        simulation.target,
+        wins as f64 / games * 100.0
+    );
+    println!("max tile distribution:");
+    for (tile, count) in max_tiles {
+        println!(
+            "  {tile:>6}: {count} ({:.1}%)",
+            f64::from(count) / games * 100.0
+        );
+    }
+}
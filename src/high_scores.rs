@@ -0,0 +1,176 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{colors, daily::Date, mode, replay, storage, FontSpec, GameOverEvent};
+
+pub const HIGH_SCORES_FILE: &str = "high_scores.json";
+
+/// Games kept in the table.
+const HIGH_SCORES_LEN: usize = 10;
+
+/// The best games played, kept in `HIGH_SCORES_FILE` and shown with F12.
+pub struct HighScoresPlugin;
+
+impl Plugin for HighScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_high_scores())
+            .add_system(
+                record_high_score
+                    .run_if(replay::not_replaying)
+                    .run_if(mode::not_practice),
+            )
+            .add_system(toggle_high_scores_panel);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u32,
+    /// When the game ended, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+/// Best first, at most `HIGH_SCORES_LEN` long.
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HighScores {
+    pub games: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Adds a game if it makes the table, returns whether it did. Ties go
+    /// to the game already in the table.
+    fn insert(&mut self, game: HighScore) -> bool {
+        let index = self
+            .games
+            .iter()
+            .position(|entry| entry.score < game.score)
+            .unwrap_or(self.games.len());
+        if index >= HIGH_SCORES_LEN {
+            return false;
+        }
+
+        self.games.insert(index, game);
+        self.games.truncate(HIGH_SCORES_LEN);
+        true
+    }
+}
+
+/// A missing or corrupt file starts an empty table, and a hand edited one
+/// is put back in order.
+fn load_high_scores() -> HighScores {
+    let mut high_scores = storage::load::<HighScores>(HIGH_SCORES_FILE);
+    high_scores
+        .games
+        .sort_by_key(|game| std::cmp::Reverse(game.score));
+    high_scores.games.truncate(HIGH_SCORES_LEN);
+    high_scores
+}
+
+fn record_high_score(
+    mut game_over_events: EventReader<GameOverEvent>,
+    mut high_scores: ResMut<HighScores>,
+) {
+    for event in game_over_events.iter() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+        let game = HighScore {
+            score: event.score,
+            max_tile: event.max_tile,
+            moves: event.moves,
+            timestamp,
+        };
+
+        if event.score > 0 && high_scores.insert(game) {
+            storage::save(HIGH_SCORES_FILE, &*high_scores);
+        }
+    }
+}
+
+#[derive(Component)]
+struct HighScoresPanel;
+
+fn toggle_high_scores_panel(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    high_scores: Res<HighScores>,
+    font_spec: Res<FontSpec>,
+    panels: Query<Entity, With<HighScoresPanel>>,
+) {
+    if !input.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    if let Ok(panel) = panels.get_single() {
+        commands.entity(panel).despawn_recursive();
+        return;
+    }
+
+    let lines = if high_scores.games.is_empty() {
+        vec!["No games yet".to_string()]
+    } else {
+        high_scores
+            .games
+            .iter()
+            .enumerate()
+            .map(|(index, game)| {
+                format!(
+                    "{}. {}  tile {}, {} moves, {}",
+                    index + 1,
+                    game.score,
+                    game.max_tile,
+                    game.moves,
+                    Date::from_timestamp(game.timestamp)
+                )
+            })
+            .collect()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        right: Val::Px(50.0),
+                        bottom: Val::Px(50.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.0)),
+                    gap: Size::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(colors::SCORE_BOX),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            HighScoresPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "High Scores",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            ));
+            for line in lines {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: font_spec.family.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+        });
+}
@@ -0,0 +1,90 @@
+use std::io::{self, BufRead, Write};
+
+use b2048::{four_chance_for_score, has_any_move, shift_grid, spawn_tile, BoardShift, Grid};
+use rand::prelude::*;
+use rand_chacha::ChaCha12Rng;
+use serde::Serialize;
+
+use crate::cli::Args;
+
+/// What an external player sees after every move, one JSON object per line.
+#[derive(Serialize)]
+struct BoardState {
+    score: u32,
+    moves: u32,
+    won: bool,
+    /// Set on the last line, once no move is left or the input ended.
+    over: bool,
+    /// Rows from the top, `null` for an empty cell.
+    grid: Vec<Vec<Option<u32>>>,
+}
+
+/// Plays one game driven from stdin, for bots living in another process.
+/// Each line of input is a direction, `left`, `right`, `up` or `down`, with
+/// or without JSON quotes. A move that changes nothing is answered with the
+/// same board, and the end of the input or a line that isn't a direction
+/// ends the game.
+pub fn run(args: &Args) {
+    let config = args.board_config();
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let target = args.target.unwrap_or(2048);
+    let four_chance = |score| {
+        args.fixed_four_chance()
+            .unwrap_or_else(|| four_chance_for_score(score))
+    };
+
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    let mut grid = Grid::new(config.width, config.height);
+    let mut score = 0;
+    let mut moves = 0;
+
+    for _ in 0..args.start_config().starting_tiles {
+        spawn_tile(&mut grid, &mut rng, four_chance(score));
+    }
+
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        let over = !has_any_move(&grid);
+        print_state(&grid, score, moves, target, over);
+        if over {
+            return;
+        }
+
+        let Some(dir) = lines
+            .next()
+            .and_then(Result::ok)
+            .and_then(|line| parse_direction(&line))
+        else {
+            print_state(&grid, score, moves, target, true);
+            return;
+        };
+
+        let (next, gain, moved) = shift_grid(&grid, dir);
+        if moved {
+            grid = next;
+            score += gain;
+            moves += 1;
+            spawn_tile(&mut grid, &mut rng, four_chance(score));
+        }
+    }
+}
+
+fn parse_direction(line: &str) -> Option<BoardShift> {
+    let direction = line.trim().trim_matches('"').to_lowercase();
+    serde_json::from_value(serde_json::Value::String(direction)).ok()
+}
+
+fn print_state(grid: &Grid, score: u32, moves: u32, target: u32, over: bool) {
+    let state = BoardState {
+        score,
+        moves,
+        won: grid.has_won(target),
+        over,
+        grid: grid.rows(),
+    };
+    let json = serde_json::to_string(&state).expect("board state should serialize");
+
+    let mut stdout = io::stdout().lock();
+    // a closed stdout just means nobody is listening any more
+    let _ = writeln!(stdout, "{json}").and_then(|_| stdout.flush());
+}
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::BoardShift;
+
+/// Overrides are read from here; keys left out keep their defaults.
+pub const KEYBINDINGS_FILE: &str = "keybindings.json";
+
+/// Keys bound to each action. Every action accepts any of its keys.
+#[derive(Debug, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub left: Vec<KeyCode>,
+    pub right: Vec<KeyCode>,
+    pub up: Vec<KeyCode>,
+    pub down: Vec<KeyCode>,
+    pub undo: Vec<KeyCode>,
+    pub pause: Vec<KeyCode>,
+    pub restart: Vec<KeyCode>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            left: vec![KeyCode::Left, KeyCode::A],
+            right: vec![KeyCode::Right, KeyCode::D],
+            up: vec![KeyCode::Up, KeyCode::W],
+            down: vec![KeyCode::Down, KeyCode::S],
+            undo: vec![KeyCode::U],
+            pause: vec![KeyCode::P],
+            restart: vec![KeyCode::R],
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn shift_for(&self, key: KeyCode) -> Option<BoardShift> {
+        [
+            (&self.left, BoardShift::Left),
+            (&self.right, BoardShift::Right),
+            (&self.up, BoardShift::Up),
+            (&self.down, BoardShift::Down),
+        ]
+        .into_iter()
+        .find(|(keys, _)| keys.contains(&key))
+        .map(|(_, direction)| direction)
+    }
+
+    /// The direction to shift for the keys pressed in one frame, along with
+    /// the key that asked for it. Opposite directions pressed together
+    /// cancel out, so Left with Right does nothing and Left, Right and Up
+    /// shifts up. When a horizontal and a vertical direction remain the
+    /// horizontal one wins, whatever order the keys come in.
+    pub fn pressed_shift(
+        &self,
+        keys: impl IntoIterator<Item = KeyCode>,
+    ) -> Option<(KeyCode, BoardShift)> {
+        let (mut left, mut right, mut up, mut down) = (None, None, None, None);
+        for key in keys {
+            let slot = match self.shift_for(key) {
+                Some(BoardShift::Left) => &mut left,
+                Some(BoardShift::Right) => &mut right,
+                Some(BoardShift::Up) => &mut up,
+                Some(BoardShift::Down) => &mut down,
+                None => continue,
+            };
+            slot.get_or_insert(key);
+        }
+
+        let horizontal = match (left, right) {
+            (Some(key), None) => Some((key, BoardShift::Left)),
+            (None, Some(key)) => Some((key, BoardShift::Right)),
+            _ => None,
+        };
+        let vertical = match (up, down) {
+            (Some(key), None) => Some((key, BoardShift::Up)),
+            (None, Some(key)) => Some((key, BoardShift::Down)),
+            _ => None,
+        };
+
+        horizontal.or(vertical)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pressed(keys: &[KeyCode]) -> Option<(KeyCode, BoardShift)> {
+        KeyBindings::default().pressed_shift(keys.iter().copied())
+    }
+
+    #[test]
+    fn opposite_directions_cancel() {
+        assert_eq!(pressed(&[KeyCode::Left, KeyCode::Right]), None);
+    }
+
+    #[test]
+    fn cancelled_pair_leaves_the_other_axis() {
+        assert_eq!(
+            pressed(&[KeyCode::Left, KeyCode::Right, KeyCode::Up]),
+            Some((KeyCode::Up, BoardShift::Up))
+        );
+    }
+
+    #[test]
+    fn horizontal_beats_vertical_in_any_order() {
+        assert_eq!(
+            pressed(&[KeyCode::Up, KeyCode::Left]),
+            Some((KeyCode::Left, BoardShift::Left))
+        );
+        assert_eq!(
+            pressed(&[KeyCode::Left, KeyCode::Up]),
+            Some((KeyCode::Left, BoardShift::Left))
+        );
+    }
+
+    #[test]
+    fn arrow_and_wasd_of_one_direction_shift_once() {
+        assert_eq!(
+            pressed(&[KeyCode::Left, KeyCode::A]),
+            Some((KeyCode::Left, BoardShift::Left))
+        );
+        // the first key of a direction is the one held for repeats
+        assert_eq!(
+            pressed(&[KeyCode::A, KeyCode::Left]),
+            Some((KeyCode::A, BoardShift::Left))
+        );
+    }
+
+    #[test]
+    fn unbound_keys_are_ignored() {
+        assert_eq!(pressed(&[KeyCode::Q, KeyCode::Space]), None);
+    }
+}
@@ -0,0 +1,34 @@
+//! The rules of 2048 without the game around them: a board of tile values,
+//! the four moves and the win and lose checks. Nothing here depends on
+//! Bevy, so the model can be reused by solvers, servers or tests.
+//!
+//! The `bevy` feature, on by default, builds the game binary and adds the
+//! Bevy derives it needs here. Depend on the library with
+//! `default-features = false` to leave Bevy out.
+
+use serde::{Deserialize, Serialize};
+
+pub mod grid;
+pub mod simulate;
+
+pub use grid::{has_any_move, plan_shift, shift_grid, Grid, Shift, Slide};
+pub use simulate::{
+    four_chance_for_score, simulate_game, spawn_tile, GameResult, SimulationConfig,
+};
+
+/// A cell of the board, `(0, 0)` being the bottom left corner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
+pub struct Position {
+    pub x: u8,
+    pub y: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BoardShift {
+    Left,
+    Right,
+    Up,
+    Down,
+}
@@ -1,41 +1,208 @@
 use bevy_easings::*;
-use std::{cmp::Ordering, collections::HashMap, ops::Range};
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Duration,
+};
 
-use bevy::prelude::*;
+use b2048::{four_chance_for_score, grid, BoardShift, Position};
+use bevy::{
+    prelude::*,
+    transform::TransformSystem,
+    window::{PrimaryWindow, WindowFocused},
+};
+use clap::ValueEnum;
 use itertools::Itertools;
 use rand::prelude::*;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
+mod ai;
+mod announce;
+mod audio;
+mod cli;
 mod colors;
+mod compact;
+mod daily;
+mod debug;
+mod display;
+mod edge_arrows;
+mod fps;
+mod headless;
+mod high_scores;
+mod io;
+mod keybindings;
+mod mode;
+mod particles;
+mod power_ups;
+mod practice;
+mod preview;
+mod quit;
+mod replay;
+mod savegame;
+mod settings;
+mod state_scoped;
+mod stats;
+mod storage;
+mod theme;
+mod tooltip;
 mod ui;
+mod versus;
 
 fn main() {
+    let args = cli::Args::parse_valid();
+
+    if let Some(games) = args.headless {
+        headless::run(&args, games);
+        return;
+    }
+    if args.io {
+        io::run(&args);
+        return;
+    }
+
     App::new()
-        .insert_resource(ClearColor(Color::hex("#1f2638").unwrap()))
+        .insert_resource(args.board_config())
+        .insert_resource(args.seed_config())
+        .insert_resource(args.start_config())
+        .insert_resource(args.spawn_bias)
+        .insert_resource(args.undo_config())
+        .insert_resource(args.autosave_config())
+        .insert_resource(args.toast_config())
+        .insert_resource(args)
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "2048".to_string(),
                 ..default()
             }),
+            // quit::QuitPlugin asks before closing a game in progress
+            close_when_requested: false,
             ..default()
         }))
         .add_plugin(EasingsPlugin)
+        .add_system(custom_ease_system::<TileScale>)
+        .add_system(custom_ease_system::<TileFlash>)
+        .add_system(apply_tile_flash.in_base_set(CoreSet::PostUpdate))
+        .add_system(
+            apply_tile_scale
+                .in_base_set(CoreSet::PostUpdate)
+                .before(TransformSystem::TransformPropagate),
+        )
         .add_state::<GameState>()
+        .add_plugin(state_scoped::StateScopedPlugin::<GameState>::default())
         .add_plugin(ui::GameUIPlugin)
+        .add_plugin(replay::ReplayPlugin)
+        .add_plugin(ai::AiPlugin)
+        .add_plugin(stats::StatsPlugin)
+        .add_plugin(high_scores::HighScoresPlugin)
+        .add_plugin(display::DisplayPlugin)
+        .add_plugin(audio::GameAudioPlugin)
+        .add_plugin(theme::ThemePlugin)
+        .add_plugin(savegame::SaveGamePlugin)
+        .add_plugin(quit::QuitPlugin)
+        .add_plugin(settings::SettingsPlugin)
+        .add_plugin(mode::GameModePlugin)
+        .add_plugin(practice::PracticePlugin)
+        .add_plugin(tooltip::TooltipPlugin)
+        .add_plugin(debug::DebugPlugin)
+        .add_plugin(particles::ParticlesPlugin)
+        .add_plugin(fps::FpsPlugin)
+        .add_plugin(announce::AnnouncementsPlugin)
+        .add_plugin(compact::CompactPlugin)
+        .add_plugin(edge_arrows::EdgeArrowsPlugin)
+        .add_plugin(power_ups::PowerUpsPlugin)
+        .add_plugin(versus::VersusPlugin)
+        .add_plugin(preview::PreviewPlugin)
         .init_resource::<FontSpec>()
         .init_resource::<Game>()
+        .init_resource::<UndoStack>()
+        .init_resource::<MoveHistory>()
+        .init_resource::<GameRng>()
+        .init_resource::<RetrySeed>()
+        .init_resource::<UndoBudget>()
+        .init_resource::<GameTimer>()
+        .init_resource::<AnimationConfig>()
+        .init_resource::<InputLock>()
+        .init_resource::<RepeatConfig>()
+        .init_resource::<TileShadows>()
+        .init_resource::<Layout>()
+        .init_resource::<daily::DailyChallenge>()
+        .insert_resource(storage::load::<keybindings::KeyBindings>(
+            keybindings::KEYBINDINGS_FILE,
+        ))
         .add_event::<NewTileEvent>()
-        .add_systems((game_reset, spawn_tiles).in_schedule(OnEnter(GameState::Playing)))
+        .add_event::<ShiftEvent>()
+        .add_event::<MergeEvent>()
+        .add_event::<BumpEvent>()
+        .add_event::<GameOverEvent>()
+        .init_resource::<Resume>()
+        .add_systems(
+            (
+                game_reset.run_if(is_new_game),
+                respawn_board.run_if(is_new_game),
+                apply_system_buffers,
+                spawn_tiles.run_if(is_new_game).run_if(mode::not_practice),
+                clear_resume,
+            )
+                .chain()
+                .in_schedule(OnEnter(GameState::Playing)),
+        )
         .add_startup_systems((setup, spawn_board, apply_system_buffers).chain())
         .add_systems(
             (
-                render_tile_points,
+                keyboard_input
+                    .run_if(replay::not_replaying)
+                    .run_if(preview::not_previewing)
+                    .before(board_shift),
+                swipe_input
+                    .run_if(replay::not_replaying)
+                    .before(board_shift),
+                // before the input too, so a held key repeats as soon as the
+                // slide is over rather than a frame later
+                tick_input_lock.before(keyboard_input).before(board_shift),
                 board_shift,
-                render_tiles,
-                new_tile_handler,
+                undo.run_if(replay::not_replaying),
+                win_check.run_if(mode::not_practice),
                 end_game,
+                tick_game_timer,
+            )
+                .in_set(OnUpdate(GameState::Playing)),
+        )
+        // the boards of `versus` move, spawn and get painted by these too
+        .add_systems(
+            (
+                render_tile_points,
+                render_tile_color,
+                render_tiles,
+                new_tile_handler.run_if(spawns_tiles),
+                merge_pulse,
+                merge_flash,
+                spawn_score_popups,
+                bump_board,
             )
+                .distributive_run_if(boards_in_play),
+        )
+        .add_system(start_merge_removal.after(board_shift))
+        .add_system(finish_merge_removal)
+        .add_system(
+            finish_no_moves_toast
+                .after(end_game)
                 .in_set(OnUpdate(GameState::Playing)),
         )
+        .add_system(continue_playing.in_set(OnUpdate(GameState::Won)))
+        .add_system(toggle_pause)
+        .add_system(restart.run_if(replay::not_replaying))
+        .add_system(animate_score_popups)
+        .add_system(fade_spawn_glow)
+        .add_system(cycle_animation_speed)
+        .add_system(snap_tiles_on_focus_loss)
+        .add_system(cycle_layout)
+        // ahead of `Update`, so no system there holds on to the old board
+        .add_system(rebuild_board.in_base_set(CoreSet::PreUpdate))
+        .add_system(toggle_tile_shadows)
+        .add_system(sync_tile_shadows.after(toggle_tile_shadows))
+        .init_resource::<VictoryBadges>()
+        .add_system(toggle_victory_badges)
+        .add_system(sync_victory_badges.after(toggle_victory_badges))
         .run()
 }
 
@@ -46,20 +213,371 @@ fn setup(mut commands: Commands) {
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, States)]
 enum GameState {
     #[default]
+    Menu,
     Playing,
+    Paused,
+    Won,
     GameOver,
+    ConfirmQuit,
+    Settings,
+    /// Two players side by side, see `versus`.
+    Versus,
+}
+
+/// Asks for a random tile on the board that just moved.
+struct NewTileEvent(BoardId);
+
+/// Sent by `board_shift` for the surviving tile of every merge.
+struct MergeEvent {
+    entity: Entity,
+    points: u32,
+    position: Position,
+    board: BoardId,
+}
+
+/// Sent by `board_shift` when a move is pressed into a wall and nothing
+/// moves, once for every board.
+struct BumpEvent {
+    dir: BoardShift,
+    board: BoardId,
 }
 
-struct NewTileEvent;
+/// Sent with the final numbers whenever a game ends, by running out of
+/// moves or time or by the End Game button, as it switches to
+/// `GameState::GameOver`.
+#[derive(Clone)]
+struct GameOverEvent {
+    score: u32,
+    best_score: u32,
+    max_tile: u32,
+    moves: u32,
+    duration: Duration,
+}
+
+impl GameOverEvent {
+    fn new(game: &Game, timer: &GameTimer) -> Self {
+        Self {
+            score: game.score,
+            best_score: game.best_score,
+            max_tile: game.max_tile,
+            moves: game.move_count,
+            duration: timer.elapsed,
+        }
+    }
+}
 
+/// Set when returning to `Playing` from an overlay state, so the board
+/// isn't reset on `OnEnter(GameState::Playing)`.
 #[derive(Default, Resource)]
+struct Resume(bool);
+
+fn boards_in_play(state: Res<State<GameState>>) -> bool {
+    matches!(state.0, GameState::Playing | GameState::Versus)
+}
+
+/// Practice places its tiles by hand, versus always spawns them.
+fn spawns_tiles(state: Res<State<GameState>>, mode: Res<mode::GameMode>) -> bool {
+    state.0 == GameState::Versus || *mode != mode::GameMode::Practice
+}
+
+fn is_new_game(resume: Res<Resume>) -> bool {
+    !resume.0
+}
+
+fn clear_resume(mut resume: ResMut<Resume>) {
+    resume.0 = false;
+}
+
+#[derive(Resource)]
 struct Game {
     score: u32,
     best_score: u32,
+    /// Chance of a 4 while `scale_four_chance` is off.
+    four_spawn_chance: f32,
+    /// Picks the chance of a 4 with `four_chance_for_score` instead.
+    scale_four_chance: bool,
+    win_target: u32,
+    keep_playing: bool,
+    move_count: u32,
+    max_tile: u32,
+    /// Consecutive moves that merged, see `Game::combo_multiplier`.
+    combo: u32,
+    shuffles_left: u32,
+    removals_left: u32,
+}
+
+/// Highest multiplier a combo can reach.
+const MAX_COMBO: u32 = 4;
+
+impl FromWorld for Game {
+    fn from_world(world: &mut World) -> Self {
+        let scores: storage::Scores = storage::load(storage::SCORES_FILE);
+        // the stats keep their own best, which may be ahead if the scores
+        // file was lost, so start from whichever is higher
+        let stats_best = world
+            .get_resource::<stats::Stats>()
+            .map_or(0, |stats| stats.best_score);
+        let args = world.resource::<cli::Args>();
+        let (four_spawn_chance, scale_four_chance) = args.four_chance_rules();
+
+        Self {
+            score: 0,
+            best_score: scores.best_score.max(stats_best),
+            four_spawn_chance,
+            scale_four_chance,
+            win_target: args.target.unwrap_or(2048),
+            keep_playing: false,
+            move_count: 0,
+            max_tile: 0,
+            combo: 0,
+            shuffles_left: power_ups::SHUFFLES_PER_GAME,
+            removals_left: power_ups::REMOVALS_PER_GAME,
+        }
+    }
+}
+
+impl Game {
+    /// Every move that merges extends the combo by one and multiplies its
+    /// points by the combo, up to `MAX_COMBO`. A move that slides without
+    /// merging resets it, as does a new game; undo restores it.
+    fn combo_multiplier(&self) -> u32 {
+        self.combo.clamp(1, MAX_COMBO)
+    }
+
+    /// Chance of a 4 on a board that has scored `score`, which is
+    /// `Game::score` but for boards with a `BoardGame` of their own.
+    fn four_chance(&self, score: u32) -> f32 {
+        if self.scale_four_chance {
+            four_chance_for_score(score)
+        } else {
+            self.four_spawn_chance
+        }
+    }
+
+    fn roll_tile_value(&self, score: u32, rng: &mut impl Rng) -> u32 {
+        if rng.gen_bool(f64::from(self.four_chance(score).clamp(0.0, 1.0))) {
+            4
+        } else {
+            2
+        }
+    }
+}
+
+/// Score and rng of a board playing a game of its own, as the boards of
+/// `versus` do, rather than sharing `Game` and `GameRng` with the others.
+/// Sits on the parent of the board along with its `BoardId`, so it outlives
+/// `rebuild_board`; the rules still come from `Game`.
+#[derive(Component)]
+struct BoardGame {
+    score: u32,
+    rng: ChaCha12Rng,
+}
+
+/// State of the board before a move, used to undo it.
+struct Snapshot {
+    tiles: Vec<(BoardId, Position, u32)>,
+    score: u32,
+    combo: u32,
+    max_tile: u32,
+    rng: ChaCha12Rng,
+}
+
+const UNDO_LIMIT: usize = 10;
+
+#[derive(Default, Resource)]
+struct UndoStack(VecDeque<Snapshot>);
+
+impl UndoStack {
+    fn push(&mut self, snapshot: Snapshot) {
+        if self.0.len() == UNDO_LIMIT {
+            self.0.pop_front();
+        }
+        self.0.push_back(snapshot);
+    }
+
+    fn pop(&mut self) -> Option<Snapshot> {
+        self.0.pop_back()
+    }
+}
+
+/// Moves kept by `MoveHistory`.
+const MOVE_HISTORY_LEN: usize = 8;
+
+/// Recent moves and the points each one scored, newest last.
+#[derive(Default, Resource)]
+struct MoveHistory(VecDeque<(BoardShift, u32)>);
+
+impl MoveHistory {
+    fn push(&mut self, direction: BoardShift, points: u32) {
+        if self.0.len() == MOVE_HISTORY_LEN {
+            self.0.pop_front();
+        }
+        self.0.push_back((direction, points));
+    }
+}
+
+/// How a new game starts.
+#[derive(Resource)]
+struct StartConfig {
+    /// Tiles on the board before the first move, at most one per cell.
+    starting_tiles: usize,
+}
+
+impl Default for StartConfig {
+    fn default() -> Self {
+        Self { starting_tiles: 2 }
+    }
+}
+
+/// Where new tiles tend to land, set with `--spawn-bias`. Any empty cell can
+/// still get the tile, the bias only weighs the draw.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Resource, Serialize, Deserialize, ValueEnum,
+)]
+#[serde(rename_all = "kebab-case")]
+enum SpawnBias {
+    /// Every empty cell is as likely, as in the classic game.
+    #[default]
+    Uniform,
+    /// Cells far from the largest tile are likelier, which makes small
+    /// tiles harder to feed into it.
+    AwayFromLarge,
+    /// Cells close to a corner are likelier.
+    Corners,
+}
+
+impl SpawnBias {
+    /// Relative chance of a new tile landing on the empty cell `pos`.
+    fn weight(self, board: &Board, pos: Position, tiles: &[(Position, u32)]) -> f32 {
+        let distance = |a: Position, b: Position| a.x.abs_diff(b.x) + a.y.abs_diff(b.y);
+
+        match self {
+            SpawnBias::Uniform => 1.0,
+            SpawnBias::AwayFromLarge => {
+                let largest = tiles.iter().map(|(_, value)| *value).max();
+                let nearest = tiles
+                    .iter()
+                    .filter(|(_, value)| Some(*value) == largest)
+                    .map(|(tile, _)| distance(pos, *tile))
+                    .min()
+                    .unwrap_or(0);
+
+                1.0 + f32::from(nearest)
+            }
+            SpawnBias::Corners => {
+                let to_edge = |at: u8, cells: u8| at.min(cells - 1 - at);
+                let nearest = to_edge(pos.x, board.width) + to_edge(pos.y, board.height);
+
+                1.0 / (1.0 + f32::from(nearest))
+            }
+        }
+    }
+}
+
+/// Fixes the seed of every game, e.g. for tests and replays.
+/// `None` draws a fresh seed from entropy for each new game.
+#[derive(Default, Resource)]
+struct SeedConfig {
+    seed: Option<u64>,
+}
+
+/// Set by the Retry button so the next game reuses the seed of the last
+/// one, repeating its starting tiles and spawn order.
+#[derive(Default, Resource)]
+struct RetrySeed(bool);
+
+/// Undos allowed per game, `None` for as many as there are snapshots.
+#[derive(Resource)]
+struct UndoConfig {
+    per_game: Option<u32>,
+}
+
+impl Default for UndoConfig {
+    fn default() -> Self {
+        Self { per_game: Some(3) }
+    }
+}
+
+/// Undos left in the current game, refilled from `UndoConfig` by
+/// `game_reset`. `None` is unlimited.
+#[derive(Default, Resource)]
+struct UndoBudget {
+    remaining: Option<u32>,
+}
+
+/// Source of all gameplay randomness, re-seeded by `game_reset`.
+/// ChaCha12 is what `StdRng` wraps; naming it lets the state be saved.
+#[derive(Resource)]
+struct GameRng {
+    seed: u64,
+    rng: ChaCha12Rng,
+}
+
+impl GameRng {
+    fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl FromWorld for GameRng {
+    fn from_world(world: &mut World) -> Self {
+        let config = world.resource::<SeedConfig>();
+
+        Self::new(config.seed.unwrap_or_else(rand::random))
+    }
 }
 
-const TILE_SIZE: f32 = 80.0;
-const TILE_PADDING: f32 = 10.0;
+/// Time spent in `Playing` this game.
+#[derive(Default, Resource)]
+struct GameTimer {
+    elapsed: Duration,
+}
+
+fn tick_game_timer(time: Res<Time>, mut timer: ResMut<GameTimer>) {
+    timer.elapsed += time.delta();
+}
+
+/// Size of a tile and the gap around it, the board is sized to fit them.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+struct Layout {
+    tile_size: f32,
+    tile_padding: f32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        LAYOUTS[0]
+    }
+}
+
+/// Layouts cycled through with L, the denser ones fit big boards on small
+/// screens.
+const LAYOUTS: [Layout; 3] = [
+    Layout {
+        tile_size: 80.0,
+        tile_padding: 10.0,
+    },
+    Layout {
+        tile_size: 64.0,
+        tile_padding: 6.0,
+    },
+    Layout {
+        tile_size: 48.0,
+        tile_padding: 4.0,
+    },
+];
+
+fn cycle_layout(input: Res<Input<KeyCode>>, mut layout: ResMut<Layout>) {
+    if !input.just_pressed(KeyCode::L) {
+        return;
+    }
+
+    let current = LAYOUTS.iter().position(|candidate| *candidate == *layout);
+    *layout = LAYOUTS[current.map_or(0, |index| (index + 1) % LAYOUTS.len())];
+}
 
 #[derive(Debug, Component, PartialEq)]
 struct Points {
@@ -69,6 +587,59 @@ struct Points {
 #[derive(Component)]
 struct TileText;
 
+/// Darker copy of a tile offset down and right, so tiles read as raised.
+#[derive(Component)]
+struct TileShadow;
+
+const TILE_SHADOW_OFFSET: Vec2 = Vec2::new(3.0, -3.0);
+
+/// Whether tiles cast a shadow, on by default and toggled with F9.
+#[derive(Resource)]
+struct TileShadows(bool);
+
+impl Default for TileShadows {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Empty cell sprite drawn under the tiles.
+#[derive(Component)]
+struct TilePlaceholder;
+
+/// Visual scale of a tile, eased separately from the slide easing on
+/// `Transform` so pop animations don't fight with movement.
+#[derive(Component, Default, Clone, Copy)]
+struct TileScale(f32);
+
+impl Lerp for TileScale {
+    type Scalar = f32;
+
+    fn lerp(&self, other: &Self, scalar: &Self::Scalar) -> Self {
+        TileScale(self.0 + (other.0 - self.0) * scalar)
+    }
+}
+
+/// How far a tile is lit up towards white, eased back to 0 after a merge.
+#[derive(Component, Default, Clone, Copy)]
+struct TileFlash(f32);
+
+impl Lerp for TileFlash {
+    type Scalar = f32;
+
+    fn lerp(&self, other: &Self, scalar: &Self::Scalar) -> Self {
+        TileFlash(self.0 + (other.0 - self.0) * scalar)
+    }
+}
+
+/// Share of white in a tile at the peak of its flash.
+const FLASH_STRENGTH: f32 = 0.6;
+
+const SPAWN_SCALE: f32 = 0.01;
+const SPAWN_DURATION: Duration = Duration::from_millis(120);
+/// Delay between the pops of the starting tiles.
+const START_TILE_STAGGER: Duration = Duration::from_millis(80);
+
 #[derive(Resource)]
 struct FontSpec {
     family: Handle<Font>,
@@ -83,110 +654,371 @@ impl FromWorld for FontSpec {
     }
 }
 
-#[derive(Debug, Component, PartialEq, Eq, Hash)]
-struct Position {
-    x: u8,
-    y: u8,
+/// Size of the next board, picked in the menu.
+#[derive(Resource)]
+struct BoardConfig {
+    width: u8,
+    height: u8,
 }
 
-#[derive(Component)]
+/// Board sizes offered in the menu, as width and height.
+const BOARD_SIZES: [(u8, u8); 5] = [(3, 3), (4, 4), (5, 5), (6, 6), (4, 6)];
+
+impl BoardConfig {
+    /// Moves on to the next of `BOARD_SIZES`.
+    fn next_size(&mut self) {
+        let index = BOARD_SIZES
+            .iter()
+            .position(|size| *size == (self.width, self.height))
+            .map_or(0, |index| (index + 1) % BOARD_SIZES.len());
+        (self.width, self.height) = BOARD_SIZES[index];
+    }
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self {
+            width: 4,
+            height: 4,
+        }
+    }
+}
+
+/// Tells boards apart, and which board a tile is on. Boards and their
+/// tiles carry it as a component, so systems can work board by board.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Component)]
+struct BoardId(u8);
+
+#[derive(Clone, Component)]
 struct Board {
-    size: u8,
-    physical_size: f32,
+    /// Also inserted as a component by `build_board` and `spawn_tile`.
+    id: BoardId,
+    width: u8,
+    height: u8,
+    physical_size: Vec2,
+    /// The layout the board was built with, see `rebuild_board`.
+    layout: Layout,
 }
 
 impl Board {
-    fn new(size: u8) -> Self {
+    fn new(width: u8, height: u8, layout: Layout) -> Self {
+        let physical_length = |cells: u8| {
+            f32::from(cells) * layout.tile_size + (f32::from(cells) + 1.0) * layout.tile_padding
+        };
+
         Self {
-            size,
-            physical_size: f32::from(size) * TILE_SIZE + f32::from(size + 1) * TILE_PADDING,
+            id: BoardId::default(),
+            width,
+            height,
+            physical_size: Vec2::new(physical_length(width), physical_length(height)),
+            layout,
         }
     }
 
-    fn cell_position_to_physical(&self, pos: u8) -> f32 {
-        let offset = -self.physical_size / 2.0 + TILE_SIZE / 2.0;
+    /// Center of cell `pos` along an axis that is `physical_length` long.
+    fn cell_position_to_physical(&self, pos: u8, physical_length: f32) -> f32 {
+        let Layout {
+            tile_size,
+            tile_padding,
+        } = self.layout;
+        let offset = -physical_length / 2.0 + tile_size / 2.0;
+
+        offset + f32::from(pos) * tile_size + f32::from(pos + 1) * tile_padding
+    }
+
+    fn tile_size(&self) -> Vec2 {
+        Vec2::splat(self.layout.tile_size)
+    }
+
+    fn physical_x(&self, x: u8) -> f32 {
+        self.cell_position_to_physical(x, self.physical_size.x)
+    }
 
-        offset + f32::from(pos) * TILE_SIZE + f32::from(pos + 1) * TILE_PADDING
+    fn physical_y(&self, y: u8) -> f32 {
+        self.cell_position_to_physical(y, self.physical_size.y)
     }
 
     fn to_vec2(&self) -> Vec2 {
-        Vec2::new(self.physical_size, self.physical_size)
+        self.physical_size
+    }
+
+    fn cells(&self) -> usize {
+        usize::from(self.width) * usize::from(self.height)
+    }
+
+    /// Cell under `point`, given relative to the board center. Points in the
+    /// padding between cells belong to none.
+    fn cell_at(&self, point: Vec2) -> Option<Position> {
+        let within = |center: f32, coordinate: f32| {
+            (coordinate - center).abs() <= self.layout.tile_size / 2.0
+        };
+        let x = (0..self.width).find(|x| within(self.physical_x(*x), point.x))?;
+        let y = (0..self.height).find(|y| within(self.physical_y(*y), point.y))?;
+
+        Some(Position { x, y })
+    }
+
+    fn is_same_size(&self, config: &BoardConfig) -> bool {
+        self.width == config.width && self.height == config.height
     }
+
+    /// Grid of the tiles on this board, out of `tiles` from every board.
+    fn grid<'a>(
+        &self,
+        tiles: impl IntoIterator<Item = (&'a Position, &'a Points, &'a BoardId)>,
+    ) -> grid::Grid {
+        grid::Grid::from_tiles(
+            self.width,
+            self.height,
+            tiles
+                .into_iter()
+                .filter(|(.., id)| **id == self.id)
+                .map(|(pos, points, _)| (*pos, points.value)),
+        )
+    }
+}
+
+fn spawn_board(
+    mut commands: Commands,
+    config: Res<BoardConfig>,
+    layout: Res<Layout>,
+    theme: Res<colors::Theme>,
+) {
+    let board = Board::new(config.width, config.height, *layout);
+    build_board(&mut commands, board, theme.palette());
 }
 
-fn spawn_board(mut commands: Commands) {
-    let board = Board::new(4);
+/// Spawns `board` with its empty cells and no tiles.
+fn build_board(commands: &mut Commands, board: Board, palette: &colors::Palette) -> Entity {
+    let id = board.id;
 
     commands
         .spawn(SpriteBundle {
             sprite: Sprite {
-                color: colors::BOARD,
+                color: palette.board,
                 custom_size: Some(board.to_vec2()),
                 ..default()
             },
             ..default()
         })
         .with_children(|builder| {
-            for tile in (0..board.size).cartesian_product(0..board.size) {
+            for tile in (0..board.width).cartesian_product(0..board.height) {
                 let sprite = Sprite {
-                    color: colors::TILE_PLACEHOLDER,
-                    custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                    color: palette.tile_placeholder,
+                    custom_size: Some(board.tile_size()),
                     ..default()
                 };
 
-                builder.spawn(SpriteBundle {
-                    sprite,
-                    transform: Transform::from_xyz(
-                        board.cell_position_to_physical(tile.0),
-                        board.cell_position_to_physical(tile.1),
-                        1.0,
-                    ),
-                    ..default()
-                });
+                builder
+                    .spawn(SpriteBundle {
+                        sprite,
+                        transform: Transform::from_xyz(
+                            board.physical_x(tile.0),
+                            board.physical_y(tile.1),
+                            // below the tiles, leaving room for their glow
+                            0.5,
+                        ),
+                        ..default()
+                    })
+                    .insert(TilePlaceholder);
             }
         })
-        .insert(board);
+        .insert((board, id))
+        .id()
+}
+
+/// Rebuilds the board when a different size was picked in the menu.
+fn respawn_board(
+    mut commands: Commands,
+    boards: Query<(Entity, &Board)>,
+    config: Res<BoardConfig>,
+    layout: Res<Layout>,
+    theme: Res<colors::Theme>,
+) {
+    for (entity, board) in boards.iter() {
+        if board.is_same_size(&config) && board.layout == *layout {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        let respawned = Board {
+            id: board.id,
+            ..Board::new(config.width, config.height, *layout)
+        };
+        build_board(&mut commands, respawned, theme.palette());
+    }
 }
 
-fn spawn_tiles(mut commands: Commands, query_board: Query<&Board>, font_spec: Res<FontSpec>) {
-    let board = query_board.single();
+/// Builds every board again in a new layout and puts the tiles back where
+/// they were, without touching the game. A board that is the child of
+/// another entity stays its child.
+fn rebuild_board(
+    mut commands: Commands,
+    layout: Res<Layout>,
+    boards: Query<(Entity, &Board, Option<&Parent>)>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    font_spec: Res<FontSpec>,
+    tile_colors: colors::TileColors,
+    theme: Res<colors::Theme>,
+) {
+    if !layout.is_changed() || layout.is_added() {
+        return;
+    }
 
-    let mut rng = rand::thread_rng();
+    for (entity, board, parent) in boards.iter() {
+        if board.layout == *layout {
+            continue;
+        }
 
-    let starting_tiles: Vec<(u8, u8)> = (0..board.size)
-        .cartesian_product(0..board.size)
-        .choose_multiple(&mut rng, 2);
+        commands.entity(entity).despawn_recursive();
+        let rebuilt = Board {
+            id: board.id,
+            ..Board::new(board.width, board.height, *layout)
+        };
+        let board_entity = build_board(&mut commands, rebuilt.clone(), theme.palette());
+        if let Some(parent) = parent {
+            commands.entity(parent.get()).add_child(board_entity);
+        }
 
-    for (x, y) in starting_tiles.iter() {
-        let pos = Position { x: *x, y: *y };
-        spawn_tile(&mut commands, board, &font_spec, pos);
+        for (pos, points, _) in tiles.iter().filter(|(.., id)| **id == board.id) {
+            spawn_tile(
+                &mut commands,
+                board_entity,
+                &rebuilt,
+                &font_spec,
+                &tile_colors,
+                *pos,
+                points.value,
+            );
+        }
     }
 }
 
-fn spawn_tile(commands: &mut Commands, board: &Board, font_spec: &Res<FontSpec>, pos: Position) {
-    commands
+fn spawn_tiles(
+    mut commands: Commands,
+    query_board: Query<(Entity, &Board)>,
+    font_spec: Res<FontSpec>,
+    tile_colors: colors::TileColors,
+    mut game: ResMut<Game>,
+    mut rng: ResMut<GameRng>,
+    start_config: Res<StartConfig>,
+) {
+    // in board order, so the rng draws are the same every game
+    for (board_entity, board) in query_board.iter().sorted_by_key(|(_, board)| board.id.0) {
+        let starting_tiles =
+            roll_starting_tiles(board, start_config.starting_tiles, &game, &mut rng.rng);
+        for (_, value) in &starting_tiles {
+            game.max_tile = game.max_tile.max(*value);
+        }
+
+        spawn_starting_tiles(
+            &mut commands,
+            board_entity,
+            board,
+            &font_spec,
+            &tile_colors,
+            starting_tiles,
+        );
+    }
+}
+
+/// Cells and values of the first tiles of a game, drawn from `rng` cells
+/// first so seeds keep giving the same boards.
+fn roll_starting_tiles(
+    board: &Board,
+    count: usize,
+    game: &Game,
+    rng: &mut impl Rng,
+) -> Vec<(Position, u32)> {
+    let cells: Vec<(u8, u8)> = (0..board.width)
+        .cartesian_product(0..board.height)
+        .choose_multiple(rng, count.min(board.cells()));
+
+    cells
+        .into_iter()
+        .map(|(x, y)| (Position { x, y }, game.roll_tile_value(0, rng)))
+        .collect()
+}
+
+/// Spawns the first tiles of a game, each popping in a little after the one
+/// before.
+fn spawn_starting_tiles(
+    commands: &mut Commands,
+    board_entity: Entity,
+    board: &Board,
+    font_spec: &Res<FontSpec>,
+    tile_colors: &colors::TileColors,
+    starting_tiles: Vec<(Position, u32)>,
+) {
+    for (index, (pos, value)) in starting_tiles.into_iter().enumerate() {
+        let tile = spawn_tile(
+            commands,
+            board_entity,
+            board,
+            font_spec,
+            tile_colors,
+            pos,
+            value,
+        );
+
+        if index > 0 {
+            // bevy_easings has no delay, so hold the spawn scale first. The
+            // tile is still a regular tile meanwhile, so the first move can
+            // slide or merge it right away.
+            commands
+                .entity(tile)
+                .remove::<EasingComponent<TileScale>>()
+                .insert(
+                    TileScale(SPAWN_SCALE)
+                        .ease_to(
+                            TileScale(SPAWN_SCALE),
+                            EaseFunction::QuadraticIn,
+                            EasingType::Once {
+                                duration: START_TILE_STAGGER * index as u32,
+                            },
+                        )
+                        .ease_to(
+                            TileScale(1.0),
+                            EaseFunction::BackOut,
+                            EasingType::Once {
+                                duration: SPAWN_DURATION,
+                            },
+                        ),
+                );
+        }
+    }
+}
+
+/// Tiles are children of the board, so they follow it when it bumps.
+fn spawn_tile(
+    commands: &mut Commands,
+    board_entity: Entity,
+    board: &Board,
+    font_spec: &Res<FontSpec>,
+    tile_colors: &colors::TileColors,
+    pos: Position,
+    value: u32,
+) -> Entity {
+    let tile = commands
         .spawn(SpriteBundle {
             sprite: Sprite {
-                color: colors::TILE,
-                custom_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                color: tile_colors.tile(value),
+                custom_size: Some(board.tile_size()),
                 ..default()
             },
-            transform: Transform::from_xyz(
-                board.cell_position_to_physical(pos.x),
-                board.cell_position_to_physical(pos.y),
-                1.0,
-            ),
+            transform: Transform::from_xyz(board.physical_x(pos.x), board.physical_y(pos.y), 1.0)
+                .with_scale(Vec3::new(SPAWN_SCALE, SPAWN_SCALE, 1.0)),
             ..default()
         })
+        .insert(board.id)
         .with_children(|builder| {
             let text_bundle: Text2dBundle = Text2dBundle {
                 text: Text::from_section(
-                    "2",
+                    value.to_string(),
                     TextStyle {
                         font: font_spec.family.clone(),
-                        font_size: 40.0,
-                        color: Color::BLACK,
-                        ..default()
+                        font_size: tile_font_size(value, &board.layout),
+                        color: tile_colors.text(value),
                     },
                 )
                 .with_alignment(TextAlignment::Center),
@@ -195,14 +1027,63 @@ fn spawn_tile(commands: &mut Commands, board: &Board, font_spec: &Res<FontSpec>,
             };
 
             builder.spawn(text_bundle).insert(TileText);
+            builder.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: tile_colors.shadow(value),
+                        custom_size: Some(board.tile_size()),
+                        ..default()
+                    },
+                    // above the placeholders, below the spawn glow
+                    transform: Transform::from_translation(TILE_SHADOW_OFFSET.extend(-0.4)),
+                    ..default()
+                },
+                TileShadow,
+            ));
         })
-        .insert(Points { value: 2 })
-        .insert(pos);
+        .insert(Points { value })
+        .insert(pos)
+        .insert((
+            TileScale(SPAWN_SCALE),
+            TileScale(SPAWN_SCALE).ease_to(
+                TileScale(1.0),
+                EaseFunction::BackOut,
+                EasingType::Once {
+                    duration: SPAWN_DURATION,
+                },
+            ),
+        ))
+        .id();
+
+    commands.entity(board_entity).add_child(tile);
+    tile
 }
 
-fn render_tile_points(
-    mut texts: Query<&mut Text, With<TileText>>,
-    tiles: Query<(&Points, &Children)>,
+/// Runs after the easing systems so the slide easing can't overwrite the scale.
+fn apply_tile_scale(mut tiles: Query<(&TileScale, &mut Transform)>) {
+    for (scale, mut transform) in tiles.iter_mut() {
+        transform.scale = Vec3::new(scale.0, scale.0, 1.0);
+    }
+}
+
+/// Mixes the flash into the value color, so a tile always settles back on
+/// `colors::tile_color` once the flash has eased out.
+pub(crate) fn apply_tile_flash(
+    mut tiles: Query<(&TileFlash, &Points, &mut Sprite), Changed<TileFlash>>,
+    tile_colors: colors::TileColors,
+) {
+    for (flash, points, mut sprite) in tiles.iter_mut() {
+        let base = Vec4::from(tile_colors.tile(points.value).as_rgba_f32());
+        let mix = base.lerp(Vec4::ONE, flash.0 * FLASH_STRENGTH);
+        sprite.color = Color::rgba(mix.x, mix.y, mix.z, base.w);
+    }
+}
+
+fn render_tile_points(
+    mut texts: Query<&mut Text, With<TileText>>,
+    tiles: Query<(&Points, &Children)>,
+    tile_colors: colors::TileColors,
+    layout: Res<Layout>,
 ) {
     for (points, children) in tiles.iter() {
         if let Some(entity) = children.first() {
@@ -212,233 +1093,1555 @@ fn render_tile_points(
                 .first_mut()
                 .expect("expected TextSection to exist");
             text_section.value = points.value.to_string();
+            text_section.style.font_size = tile_font_size(points.value, &layout);
+            text_section.style.color = tile_colors.text(points.value);
         };
     }
 }
 
-enum BoardShift {
-    Left,
-    Right,
-    Up,
-    Down,
+/// Shrinks the font as numbers grow so they fit within the tile, the sizes
+/// being for the default 80 pixel tiles.
+fn tile_font_size(value: u32, layout: &Layout) -> f32 {
+    let size = match value {
+        0..=99 => 40.0,
+        100..=999 => 32.0,
+        _ => 24.0,
+    };
+
+    size * layout.tile_size / LAYOUTS[0].tile_size
 }
 
-impl BoardShift {
-    fn sort(&self, a: &Position, b: &Position) -> Ordering {
-        match self {
-            BoardShift::Left => match Ord::cmp(&a.y, &b.y) {
-                Ordering::Equal => Ord::cmp(&a.x, &b.x),
-                ord => ord,
-            },
-            BoardShift::Right => match Ord::cmp(&b.y, &a.y) {
-                Ordering::Equal => Ord::cmp(&b.x, &a.x),
-                ord => ord,
-            },
-            BoardShift::Up => match Ord::cmp(&b.x, &a.x) {
-                Ordering::Equal => Ord::cmp(&b.y, &a.y),
-                ord => ord,
-            },
-            BoardShift::Down => match Ord::cmp(&a.x, &b.x) {
-                Ordering::Equal => Ord::cmp(&a.y, &b.y),
-                ord => ord,
-            },
+fn render_tile_color(
+    mut tiles: Query<(&Points, &Children, &mut Sprite), Changed<Points>>,
+    mut shadows: Query<&mut Sprite, (With<TileShadow>, Without<Points>)>,
+    tile_colors: colors::TileColors,
+) {
+    for (points, children, mut sprite) in tiles.iter_mut() {
+        sprite.color = tile_colors.tile(points.value);
+
+        let mut shadows = shadows.iter_many_mut(children);
+        while let Some(mut shadow) = shadows.fetch_next() {
+            shadow.color = tile_colors.shadow(points.value);
         }
     }
+}
 
-    fn set_column_position(&self, board_size: u8, pos: &mut Mut<Position>, col: u8) {
-        match self {
-            BoardShift::Left => pos.x = col,
-            BoardShift::Right => pos.x = board_size - 1 - col,
-            BoardShift::Up => pos.y = board_size - 1 - col,
-            BoardShift::Down => pos.y = col,
+fn toggle_tile_shadows(input: Res<Input<KeyCode>>, mut shadows: ResMut<TileShadows>) {
+    if input.just_pressed(KeyCode::F9) {
+        shadows.0 = !shadows.0;
+    }
+}
+
+/// Hides or shows every shadow on a toggle, and new ones as tiles spawn.
+fn sync_tile_shadows(
+    enabled: Res<TileShadows>,
+    mut shadows: Query<(&mut Visibility, Added<TileShadow>), With<TileShadow>>,
+) {
+    let visibility = if enabled.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    for (mut shadow, added) in shadows.iter_mut() {
+        if added || enabled.is_changed() {
+            *shadow = visibility;
         }
     }
+}
 
-    fn get_row_position(&self, pos: &Position) -> u8 {
-        match self {
-            BoardShift::Left | BoardShift::Right => pos.y,
-            BoardShift::Up | BoardShift::Down => pos.x,
+/// Whether tiles worth the win target wear a star, on by default and
+/// toggled with B.
+#[derive(Resource)]
+struct VictoryBadges(bool);
+
+impl Default for VictoryBadges {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+/// Star in the top right corner of a winning tile, two squares a quarter
+/// turn apart.
+#[derive(Component)]
+struct VictoryBadge;
+
+/// Size of the badge relative to the tile.
+const VICTORY_BADGE_SCALE: f32 = 0.18;
+
+fn toggle_victory_badges(input: Res<Input<KeyCode>>, mut badges: ResMut<VictoryBadges>) {
+    if input.just_pressed(KeyCode::B) {
+        badges.0 = !badges.0;
+    }
+}
+
+/// Adds the badge to tiles worth exactly the win target and takes it off
+/// any other, so tiles merged past the win lose it again.
+fn sync_victory_badges(
+    mut commands: Commands,
+    enabled: Res<VictoryBadges>,
+    game: Res<Game>,
+    boards: Query<&Board>,
+    tiles: Query<(Entity, &Points, &BoardId, Option<&Children>)>,
+    badges: Query<(), With<VictoryBadge>>,
+) {
+    for (entity, points, board_id, children) in tiles.iter() {
+        let badge = children.and_then(|children| {
+            children
+                .iter()
+                .copied()
+                .find(|child| badges.contains(*child))
+        });
+        let wanted = enabled.0 && points.value == game.win_target;
+
+        match (wanted, badge) {
+            (true, None) => {
+                let Some(board) = boards.iter().find(|board| board.id == *board_id) else {
+                    continue;
+                };
+                let size = board.layout.tile_size * VICTORY_BADGE_SCALE;
+                let corner = board.layout.tile_size / 2.0 - size;
+                let square = |angle: f32| SpriteBundle {
+                    sprite: Sprite {
+                        color: colors::VICTORY_BADGE,
+                        custom_size: Some(Vec2::splat(size)),
+                        ..default()
+                    },
+                    transform: Transform::from_rotation(Quat::from_rotation_z(angle)),
+                    ..default()
+                };
+
+                commands.entity(entity).with_children(|builder| {
+                    builder
+                        .spawn((
+                            SpatialBundle::from_transform(Transform::from_xyz(
+                                corner, corner, // above the text
+                                1.5,
+                            )),
+                            VictoryBadge,
+                        ))
+                        .with_children(|builder| {
+                            builder.spawn(square(0.0));
+                            builder.spawn(square(std::f32::consts::FRAC_PI_4));
+                        });
+                });
+            }
+            (false, Some(badge)) => commands.entity(badge).despawn_recursive(),
+            _ => {}
         }
     }
 }
 
-impl TryFrom<&KeyCode> for BoardShift {
-    type Error = &'static str;
+/// World position of the mouse cursor. Goes through the camera, which
+/// `display` zooms and moves to fit the window.
+fn cursor_world_position(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor = windows.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = cameras.get_single().ok()?;
 
-    fn try_from(value: &KeyCode) -> Result<Self, Self::Error> {
-        match value {
-            KeyCode::Left => Ok(Self::Left),
-            KeyCode::Right => Ok(Self::Right),
-            KeyCode::Up => Ok(Self::Up),
-            KeyCode::Down => Ok(Self::Down),
-            _ => Err("not valid key"),
+    Some(
+        camera
+            .viewport_to_world(camera_transform, cursor)?
+            .origin
+            .truncate(),
+    )
+}
+
+/// Board cell under the mouse cursor, if any.
+fn cursor_cell(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+    board: &Board,
+    board_transform: &GlobalTransform,
+) -> Option<Position> {
+    let world = cursor_world_position(windows, cameras)?;
+
+    board.cell_at(world - board_transform.translation().truncate())
+}
+
+/// Picks the dominant axis of a drag, `delta` being y-up.
+fn swipe_direction(delta: Vec2) -> Option<BoardShift> {
+    if delta.length() < SWIPE_THRESHOLD {
+        return None;
+    }
+
+    if delta.x.abs() > delta.y.abs() {
+        Some(if delta.x > 0.0 {
+            BoardShift::Right
+        } else {
+            BoardShift::Left
+        })
+    } else {
+        Some(if delta.y > 0.0 {
+            BoardShift::Up
+        } else {
+            BoardShift::Down
+        })
+    }
+}
+
+/// Minimum drag distance, in logical pixels, to count as a swipe.
+const SWIPE_THRESHOLD: f32 = 30.0;
+
+/// A request to shift the board, sent by any of the input systems.
+#[derive(Clone, Copy)]
+struct ShiftEvent(BoardShift);
+
+/// Holding a direction repeats it like keyboard key repeat, after
+/// `initial_delay` and then every `interval`.
+#[derive(Resource)]
+struct RepeatConfig {
+    initial_delay: Duration,
+    interval: Duration,
+}
+
+impl Default for RepeatConfig {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(250),
+            interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// The direction key being held and the time left until it repeats.
+struct KeyRepeat {
+    key: KeyCode,
+    direction: BoardShift,
+    timer: Timer,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn keyboard_input(
+    time: Res<Time>,
+    input: Res<Input<KeyCode>>,
+    bindings: Res<keybindings::KeyBindings>,
+    repeat_config: Res<RepeatConfig>,
+    input_lock: Res<InputLock>,
+    mut bump_events: EventReader<BumpEvent>,
+    mut held: Local<Option<KeyRepeat>>,
+    mut shift_events: EventWriter<ShiftEvent>,
+) {
+    // a single direction per frame, so an arrow and a WASD key pressed
+    // together shift once, see KeyBindings::pressed_shift for the order
+    let pressed = bindings.pressed_shift(input.get_just_pressed().copied());
+
+    if let Some((key, direction)) = pressed {
+        bump_events.clear();
+        shift_events.send(ShiftEvent(direction));
+        *held = Some(KeyRepeat {
+            key,
+            direction,
+            timer: Timer::new(repeat_config.initial_delay, TimerMode::Once),
+        });
+        return;
+    }
+
+    // a held direction that doesn't move the board stops repeating
+    let bumped = bump_events
+        .iter()
+        .any(|event| held.as_ref().map(|repeat| repeat.direction) == Some(event.dir));
+    if bumped
+        || held
+            .as_ref()
+            .is_some_and(|repeat| !input.pressed(repeat.key))
+    {
+        *held = None;
+    }
+
+    let Some(repeat) = held.as_mut() else {
+        return;
+    };
+
+    // waits for the slide to finish instead of dropping the repeat
+    if repeat.timer.tick(time.delta()).finished() && !input_lock.is_locked() {
+        shift_events.send(ShiftEvent(repeat.direction));
+        repeat.timer = Timer::new(repeat_config.interval, TimerMode::Once);
+    }
+}
+
+fn swipe_input(
+    touches: Res<Touches>,
+    mouse: Res<Input<MouseButton>>,
+    mut cursor_moved: EventReader<CursorMoved>,
+    mut cursor: Local<Vec2>,
+    mut drag_start: Local<Option<Vec2>>,
+    mut shift_events: EventWriter<ShiftEvent>,
+) {
+    if let Some(event) = cursor_moved.iter().last() {
+        *cursor = event.position;
+    }
+
+    let mut swipe = None;
+
+    if mouse.just_pressed(MouseButton::Left) {
+        *drag_start = Some(*cursor);
+    }
+    if mouse.just_released(MouseButton::Left) {
+        if let Some(start) = drag_start.take() {
+            swipe = swipe_direction(*cursor - start);
         }
     }
+
+    for touch in touches.iter_just_released() {
+        // touch positions are y-down, unlike the cursor
+        let distance = touch.distance();
+        swipe = swipe.or(swipe_direction(Vec2::new(distance.x, -distance.y)));
+    }
+
+    if let Some(board_shift) = swipe {
+        shift_events.send(ShiftEvent(board_shift));
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn board_shift(
     mut commands: Commands,
-    input: Res<Input<KeyCode>>,
-    board: Query<&Board>,
-    mut tiles: Query<(Entity, &mut Position, &mut Points)>,
+    mut shift_events: EventReader<ShiftEvent>,
+    boards: Query<&Board>,
+    mut tiles: Query<(Entity, &mut Position, &mut Points, &BoardId)>,
     mut new_tile_events: EventWriter<NewTileEvent>,
+    mut merge_events: EventWriter<MergeEvent>,
+    mut bump_events: EventWriter<BumpEvent>,
     mut game: ResMut<Game>,
+    mut undo_stack: ResMut<UndoStack>,
+    rng: Res<GameRng>,
+    mut recorder: ResMut<replay::ReplayRecorder>,
+    mut input_lock: ResMut<InputLock>,
+    animation: Res<AnimationConfig>,
+    mode: Res<mode::GameMode>,
+    mut history: ResMut<MoveHistory>,
 ) {
-    let board = board.single();
+    if input_lock.is_locked() {
+        shift_events.clear();
+        return;
+    }
 
-    let direction = input
-        .get_just_pressed()
-        .find_map(|key| BoardShift::try_from(key).ok());
+    // one shift per frame, the rest are picked up on the next one
+    let direction = shift_events.iter().next().map(|event| event.0);
 
-    if let Some(board_shift) = direction {
-        let mut it = tiles
-            .iter_mut()
-            .sorted_by(|a, b| board_shift.sort(&a.1, &b.1))
-            .peekable();
-
-        let mut col: u8 = 0;
-
-        while let Some(mut tile) = it.next() {
-            board_shift.set_column_position(board.size, &mut tile.1, col);
-
-            if let Some(next_tile) = it.peek() {
-                if board_shift.get_row_position(&next_tile.1)
-                    != board_shift.get_row_position(&tile.1)
-                {
-                    col = 0;
-                } else if next_tile.2.value != tile.2.value {
-                    col += 1;
-                } else {
-                    // merge
-                    let real_next_tile = it.next().expect("expected next tile");
-                    tile.2.value *= 2;
-                    game.score += tile.2.value;
-
-                    commands.entity(real_next_tile.0).despawn_recursive();
-
-                    if let Some(future_tile) = it.peek() {
-                        if board_shift.get_row_position(&future_tile.1)
-                            != board_shift.get_row_position(&tile.1)
-                        {
-                            col = 0;
-                        } else {
-                            col += 1;
-                        }
-                    }
-                }
-            }
+    let Some(board_shift) = direction else {
+        return;
+    };
+
+    // every board shifts the same way, and the move counts once if any of
+    // them moved
+    let shifts = boards
+        .iter()
+        .map(|board| {
+            let grid = board.grid(tiles.iter().map(|(_, pos, points, id)| (pos, points, id)));
+            (board.id, grid::plan_shift(&grid, board_shift))
+        })
+        .filter(|(_, shift)| shift.moved)
+        .collect::<Vec<_>>();
+
+    if shifts.is_empty() {
+        bump_events.send_batch(boards.iter().map(|board| BumpEvent {
+            dir: board_shift,
+            board: board.id,
+        }));
+        return;
+    }
+
+    undo_stack.push(Snapshot {
+        tiles: tiles
+            .iter()
+            .map(|(_, pos, points, id)| (*id, *pos, points.value))
+            .collect(),
+        score: game.score,
+        combo: game.combo,
+        max_tile: game.max_tile,
+        rng: rng.rng.clone(),
+    });
+
+    let mut score_gain = 0;
+    for (board_id, shift) in shifts {
+        shift_board(
+            &mut commands,
+            &mut tiles,
+            &mut merge_events,
+            board_id,
+            shift.slides,
+        );
+        game.max_tile = game.max_tile.max(shift.grid.max_value());
+        score_gain += shift.score_gain;
+        new_tile_events.send(NewTileEvent(board_id));
+    }
+
+    let mut points = 0;
+    if score_gain > 0 {
+        game.combo += 1;
+        points = score_gain * game.combo_multiplier();
+        game.score += points;
+    } else {
+        game.combo = 0;
+    }
+    game.move_count += 1;
+    history.push(board_shift, points);
+    input_lock.lock_for(&animation);
+    recorder.record(board_shift);
+
+    if *mode != mode::GameMode::Practice && game.best_score < game.score {
+        game.best_score = game.score;
+        storage::save(
+            storage::SCORES_FILE,
+            &storage::Scores {
+                best_score: game.best_score,
+            },
+        );
+    }
+}
+
+/// Moves the tiles of one board along `slides`, planned by `board_shift`
+/// or `versus`.
+fn shift_board(
+    commands: &mut Commands,
+    tiles: &mut Query<(Entity, &mut Position, &mut Points, &BoardId)>,
+    merge_events: &mut EventWriter<MergeEvent>,
+    board_id: BoardId,
+    slides: Vec<grid::Slide>,
+) {
+    let entities: HashMap<(u8, u8), Entity> = tiles
+        .iter()
+        .filter(|(.., id)| **id == board_id)
+        .map(|(entity, pos, ..)| ((pos.x, pos.y), entity))
+        .collect();
+
+    for slide in slides {
+        let entity = entities[&slide.from];
+        let (_, mut pos, mut points, _) = tiles.get_mut(entity).expect("expected tile to exist");
+        let to = Position {
+            x: slide.to.0,
+            y: slide.to.1,
+        };
+
+        if *pos != to {
+            *pos = to;
         }
-        new_tile_events.send(NewTileEvent);
 
-        if game.best_score < game.score {
-            game.best_score = game.score;
+        if let Some(merged) = slide.merged {
+            points.value = slide.value;
+            // no longer part of the board, see start_merge_removal
+            commands
+                .entity(entities[&merged])
+                .remove::<(Position, Points)>()
+                .insert(Merging { into: to });
+            merge_events.send(MergeEvent {
+                entity,
+                points: slide.value,
+                position: to,
+                board: board_id,
+            });
         }
     }
 }
 
-fn render_tiles(
+#[allow(clippy::too_many_arguments)]
+fn undo(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    bindings: Res<keybindings::KeyBindings>,
+    query_board: Query<(Entity, &Board)>,
+    tiles: Query<Entity, With<Position>>,
+    font_spec: Res<FontSpec>,
+    tile_colors: colors::TileColors,
+    mut game: ResMut<Game>,
+    mut undo_stack: ResMut<UndoStack>,
+    mut rng: ResMut<GameRng>,
+    mut recorder: ResMut<replay::ReplayRecorder>,
+    mut history: ResMut<MoveHistory>,
+    mut budget: ResMut<UndoBudget>,
+) {
+    let ctrl = input.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    if !(input.any_just_pressed(bindings.undo.iter().copied())
+        || (ctrl && input.just_pressed(KeyCode::Z)))
+    {
+        return;
+    }
+    if budget.remaining == Some(0) {
+        return;
+    }
+
+    let Some(snapshot) = undo_stack.pop() else {
+        return;
+    };
+    if let Some(remaining) = &mut budget.remaining {
+        *remaining -= 1;
+    }
+
+    for entity in tiles.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for (board_id, pos, value) in snapshot.tiles {
+        let Some((board_entity, board)) =
+            query_board.iter().find(|(_, board)| board.id == board_id)
+        else {
+            continue;
+        };
+        spawn_tile(
+            &mut commands,
+            board_entity,
+            board,
+            &font_spec,
+            &tile_colors,
+            pos,
+            value,
+        );
+    }
+
+    game.score = snapshot.score;
+    game.combo = snapshot.combo;
+    game.max_tile = snapshot.max_tile;
+    game.move_count = game.move_count.saturating_sub(1);
+    // rewinding the rng too keeps the spawn sequence, and so replays, deterministic
+    rng.rng = snapshot.rng;
+    recorder.undo();
+    history.0.pop_back();
+}
+
+/// Tile merged into the tile at `into`, or removed by a power-up when
+/// `into` is its own cell. It slides into it and shrinks away before being
+/// despawned, without a `Position` or `Points` meanwhile so nothing counts
+/// it as on the board.
+#[derive(Component)]
+struct Merging {
+    into: Position,
+}
+
+/// Ticks down to the despawn of a `Merging` tile.
+#[derive(Component)]
+struct MergeRemoval(Timer);
+
+const MERGE_REMOVAL_DURATION: Duration = Duration::from_millis(80);
+
+fn start_merge_removal(
     mut commands: Commands,
-    mut tiles: Query<(Entity, &mut Transform, &Position, Changed<Position>)>,
+    tiles: Query<(Entity, &Transform, &TileScale, &Merging, &BoardId), Added<Merging>>,
     query_board: Query<&Board>,
+    config: Res<AnimationConfig>,
+) {
+    for (entity, transform, scale, merging, board_id) in tiles.iter() {
+        let board = query_board.iter().find(|board| board.id == *board_id);
+        // snapping tiles have nothing to show
+        let Some(board) = board.filter(|_| config.slide_ms > 0) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
+
+        let easing = EasingType::Once {
+            duration: MERGE_REMOVAL_DURATION,
+        };
+        commands
+            .entity(entity)
+            .remove::<(
+                EasingChainComponent<Transform>,
+                EasingChainComponent<TileScale>,
+            )>()
+            .insert((
+                transform.ease_to(
+                    // just under the tile it merges into
+                    Transform::from_xyz(
+                        board.physical_x(merging.into.x),
+                        board.physical_y(merging.into.y),
+                        transform.translation.z - 0.1,
+                    ),
+                    EaseFunction::QuadraticIn,
+                    easing,
+                ),
+                scale.ease_to(TileScale(0.0), EaseFunction::QuadraticIn, easing),
+                MergeRemoval(Timer::new(MERGE_REMOVAL_DURATION, TimerMode::Once)),
+            ));
+    }
+}
+
+fn finish_merge_removal(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tiles: Query<(Entity, &mut MergeRemoval)>,
+) {
+    for (entity, mut removal) in tiles.iter_mut() {
+        if removal.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn merge_pulse(mut commands: Commands, mut merge_events: EventReader<MergeEvent>) {
+    for event in merge_events.iter() {
+        if let Some(mut entity) = commands.get_entity(event.entity) {
+            // inserting the chain also replaces a staggered start-tile pop
+            entity.remove::<EasingComponent<TileScale>>().insert(
+                TileScale(1.0)
+                    .ease_to(
+                        TileScale(1.2),
+                        EaseFunction::QuadraticOut,
+                        EasingType::Once {
+                            duration: Duration::from_millis(60),
+                        },
+                    )
+                    .ease_to(
+                        TileScale(1.0),
+                        EaseFunction::QuadraticIn,
+                        EasingType::Once {
+                            duration: Duration::from_millis(60),
+                        },
+                    ),
+            );
+        }
+    }
+}
+
+/// Lights the merged tile up for a couple of frames, then fades it back.
+fn merge_flash(mut commands: Commands, mut merge_events: EventReader<MergeEvent>) {
+    for event in merge_events.iter() {
+        if let Some(mut entity) = commands.get_entity(event.entity) {
+            entity.insert((
+                TileFlash(1.0),
+                TileFlash(1.0)
+                    .ease_to(
+                        TileFlash(1.0),
+                        EaseFunction::QuadraticIn,
+                        EasingType::Once {
+                            duration: Duration::from_millis(30),
+                        },
+                    )
+                    .ease_to(
+                        TileFlash(0.0),
+                        EaseFunction::QuadraticOut,
+                        EasingType::Once {
+                            duration: Duration::from_millis(150),
+                        },
+                    ),
+            ));
+        }
+    }
+}
+
+/// How far the board is nudged when a move runs into a wall.
+const BUMP_DISTANCE: f32 = 6.0;
+
+/// Nudges the board, and the tiles with it, towards a wall that blocked the
+/// move, then eases it back. Starts from the origin every time so repeated
+/// bumps don't drift the board.
+fn bump_board(
+    mut commands: Commands,
+    mut bump_events: EventReader<BumpEvent>,
+    boards: Query<(Entity, &Board)>,
+) {
+    // the last bump of each board wins
+    let bumps: HashMap<BoardId, BoardShift> = bump_events
+        .iter()
+        .map(|event| (event.board, event.dir))
+        .collect();
+
+    for (entity, board) in boards.iter() {
+        if let Some(dir) = bumps.get(&board.id) {
+            bump(&mut commands, entity, *dir);
+        }
+    }
+}
+
+fn bump(commands: &mut Commands, board: Entity, dir: BoardShift) {
+    let offset = match dir {
+        BoardShift::Left => Vec3::new(-BUMP_DISTANCE, 0.0, 0.0),
+        BoardShift::Right => Vec3::new(BUMP_DISTANCE, 0.0, 0.0),
+        BoardShift::Up => Vec3::new(0.0, BUMP_DISTANCE, 0.0),
+        BoardShift::Down => Vec3::new(0.0, -BUMP_DISTANCE, 0.0),
+    };
+
+    commands.entity(board).insert(
+        Transform::IDENTITY
+            .ease_to(
+                Transform::from_translation(offset),
+                EaseFunction::QuadraticOut,
+                EasingType::Once {
+                    duration: Duration::from_millis(50),
+                },
+            )
+            .ease_to(
+                Transform::IDENTITY,
+                EaseFunction::QuadraticInOut,
+                EasingType::Once {
+                    duration: Duration::from_millis(120),
+                },
+            ),
+    );
+}
+
+/// Floating "+N" text shown over a merge.
+#[derive(Component)]
+struct ScorePopup {
+    timer: Timer,
+    start_y: f32,
+}
+
+const SCORE_POPUP_RISE: f32 = 40.0;
+
+fn spawn_score_popups(
+    mut commands: Commands,
+    mut merge_events: EventReader<MergeEvent>,
+    query_board: Query<(Entity, &Board)>,
+    font_spec: Res<FontSpec>,
+) {
+    for event in merge_events.iter() {
+        let Some((board_entity, board)) = query_board
+            .iter()
+            .find(|(_, board)| board.id == event.board)
+        else {
+            continue;
+        };
+        let x = board.physical_x(event.position.x);
+        let y = board.physical_y(event.position.y);
+
+        let popup = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        format!("+{}", event.points),
+                        TextStyle {
+                            font: font_spec.family.clone(),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_xyz(x, y, 5.0),
+                    ..default()
+                },
+                ScorePopup {
+                    timer: Timer::from_seconds(0.6, TimerMode::Once),
+                    start_y: y,
+                },
+            ))
+            .id();
+        commands.entity(board_entity).add_child(popup);
+    }
+}
+
+fn animate_score_popups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut popups: Query<(Entity, &mut ScorePopup, &mut Transform, &mut Text)>,
 ) {
-    let board = query_board.single();
+    for (entity, mut popup, mut transform, mut text) in popups.iter_mut() {
+        popup.timer.tick(time.delta());
+
+        if popup.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let progress = popup.timer.percent();
+        transform.translation.y = popup.start_y + SCORE_POPUP_RISE * progress;
+        text.sections[0].style.color.set_a(1.0 - progress);
+    }
+}
+
+#[derive(Resource)]
+struct AnimationConfig {
+    slide_ms: u64,
+    ease: EaseFunction,
+}
 
-    for (entity, transform, pos, pos_changed) in tiles.iter_mut() {
+impl Default for AnimationConfig {
+    fn default() -> Self {
+        Self {
+            slide_ms: 100,
+            ease: EaseFunction::QuadraticInOut,
+        }
+    }
+}
+
+/// Ignores moves until the previous slide has finished, so tiles aren't
+/// re-targeted halfway through their easing.
+#[derive(Default, Resource)]
+struct InputLock(Timer);
+
+impl InputLock {
+    fn lock_for(&mut self, config: &AnimationConfig) {
+        self.0 = Timer::new(Duration::from_millis(config.slide_ms), TimerMode::Once);
+        // an empty timer only finishes once ticked, which would hold snapped
+        // moves back for a frame
+        self.0.tick(Duration::ZERO);
+    }
+
+    fn is_locked(&self) -> bool {
+        !self.0.finished()
+    }
+
+    fn tick(&mut self, delta: Duration) {
+        self.0.tick(delta);
+    }
+}
+
+fn tick_input_lock(time: Res<Time>, mut lock: ResMut<InputLock>) {
+    lock.tick(time.delta());
+}
+
+/// Slide durations cycled through with F4, 0 snaps tiles into place.
+const SLIDE_SPEEDS_MS: [u64; 4] = [100, 200, 0, 50];
+
+impl AnimationConfig {
+    fn next_speed(&mut self) {
+        let current = SLIDE_SPEEDS_MS
+            .iter()
+            .position(|&ms| ms == self.slide_ms)
+            .unwrap_or(0);
+        self.slide_ms = SLIDE_SPEEDS_MS[(current + 1) % SLIDE_SPEEDS_MS.len()];
+        info!("slide animation set to {}ms", self.slide_ms);
+    }
+}
+
+fn cycle_animation_speed(input: Res<Input<KeyCode>>, mut config: ResMut<AnimationConfig>) {
+    if input.just_pressed(KeyCode::F4) {
+        config.next_speed();
+    }
+}
+
+fn render_tiles(
+    mut commands: Commands,
+    mut tiles: Query<(
+        Entity,
+        &mut Transform,
+        &Position,
+        &BoardId,
+        Changed<Position>,
+    )>,
+    boards: Query<&Board>,
+    config: Res<AnimationConfig>,
+) {
+    for (entity, mut transform, pos, board_id, pos_changed) in tiles.iter_mut() {
         if pos_changed {
-            let x = board.cell_position_to_physical(pos.x);
-            let y = board.cell_position_to_physical(pos.y);
+            let Some(board) = boards.iter().find(|board| board.id == *board_id) else {
+                continue;
+            };
+            let x = board.physical_x(pos.x);
+            let y = board.physical_y(pos.y);
+
+            if config.slide_ms == 0 {
+                commands
+                    .entity(entity)
+                    .remove::<EasingComponent<Transform>>();
+                transform.translation.x = x;
+                transform.translation.y = y;
+                continue;
+            }
+
             commands.entity(entity).insert(transform.ease_to(
                 Transform::from_xyz(x, y, transform.translation.z),
-                EaseFunction::QuadraticInOut,
+                config.ease,
                 EasingType::Once {
-                    duration: std::time::Duration::from_millis(100),
+                    duration: Duration::from_millis(config.slide_ms),
                 },
             ));
         }
     }
 }
 
+/// Easings may stall while the window is in the background, so losing focus
+/// finishes every slide and pop at once instead of leaving tiles halfway.
+fn snap_tiles_on_focus_loss(
+    mut commands: Commands,
+    mut focus_events: EventReader<WindowFocused>,
+    mut tiles: Query<(Entity, &mut Transform, &mut TileScale, &Position, &BoardId)>,
+    query_board: Query<&Board>,
+) {
+    if !focus_events.iter().any(|event| !event.focused) {
+        return;
+    }
+
+    for (entity, mut transform, mut scale, pos, board_id) in tiles.iter_mut() {
+        let Some(board) = query_board.iter().find(|board| board.id == *board_id) else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .remove::<EasingComponent<Transform>>()
+            .remove::<EasingChainComponent<Transform>>()
+            .remove::<EasingComponent<TileScale>>()
+            .remove::<EasingChainComponent<TileScale>>();
+        transform.translation.x = board.physical_x(pos.x);
+        transform.translation.y = board.physical_y(pos.y);
+        scale.0 = 1.0;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn new_tile_handler(
     mut tile_reader: EventReader<NewTileEvent>,
     mut commands: Commands,
-    query_board: Query<&Board>,
-    tiles: Query<&Position>,
+    query_board: Query<(Entity, &Board)>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
     font_spec: Res<FontSpec>,
+    tile_colors: colors::TileColors,
+    mut game: ResMut<Game>,
+    mut rng: ResMut<GameRng>,
+    mut board_games: Query<(&BoardId, &mut BoardGame)>,
+    bias: Res<SpawnBias>,
 ) {
-    let board = query_board.single();
-
-    for _event in tile_reader.iter() {
-        let mut rng = rand::thread_rng();
+    for NewTileEvent(board_id) in tile_reader.iter() {
+        let Some((board_entity, board)) =
+            query_board.iter().find(|(_, board)| board.id == *board_id)
+        else {
+            continue;
+        };
+        // a board with a game of its own draws from its own rng
+        let mut board_game = board_games
+            .iter_mut()
+            .find(|(id, _)| *id == board_id)
+            .map(|(_, board_game)| board_game);
+        let score = board_game
+            .as_ref()
+            .map_or(game.score, |board_game| board_game.score);
+        let shared = board_game.is_none();
+        let tile_rng = match &mut board_game {
+            Some(board_game) => &mut board_game.rng,
+            None => &mut rng.rng,
+        };
+        let board_tiles = || tiles.iter().filter(|(.., id)| *id == board_id);
 
-        let possible_position: Option<Position> = (0..board.size)
-            .cartesian_product(0..board.size)
+        let empty_cells = (0..board.width)
+            .cartesian_product(0..board.height)
             .filter_map(|tile_pos| {
                 let new_pos = Position {
                     x: tile_pos.0,
                     y: tile_pos.1,
                 };
 
-                match tiles.iter().find(|pos| **pos == new_pos) {
+                match board_tiles().find(|(pos, ..)| **pos == new_pos) {
                     Some(_) => None,
                     None => Some(new_pos),
                 }
-            })
-            .choose(&mut rng);
+            });
+        // uniform keeps drawing straight from the iterator, so seeds and
+        // replays from before the bias still spawn the same tiles
+        let possible_position: Option<Position> = match *bias {
+            SpawnBias::Uniform => empty_cells.choose(tile_rng),
+            bias => {
+                let occupied = board_tiles()
+                    .map(|(pos, points, _)| (*pos, points.value))
+                    .collect::<Vec<_>>();
+
+                empty_cells
+                    .collect::<Vec<_>>()
+                    .choose_weighted(tile_rng, |pos| bias.weight(board, *pos, &occupied))
+                    .ok()
+                    .copied()
+            }
+        };
 
         if let Some(pos) = possible_position {
-            spawn_tile(&mut commands, board, &font_spec, pos);
+            let value = game.roll_tile_value(score, tile_rng);
+            if shared {
+                game.max_tile = game.max_tile.max(value);
+            }
+            let tile = spawn_tile(
+                &mut commands,
+                board_entity,
+                board,
+                &font_spec,
+                &tile_colors,
+                pos,
+                value,
+            );
+            // added after the text, which stays the first child
+            commands
+                .entity(tile)
+                .insert(JustSpawned(Timer::from_seconds(
+                    SPAWN_GLOW_SECS,
+                    TimerMode::Once,
+                )))
+                .with_children(|builder| {
+                    builder.spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: Color::rgba(1.0, 1.0, 1.0, SPAWN_GLOW_ALPHA),
+                                custom_size: Some(board.tile_size() + SPAWN_GLOW_WIDTH),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(0.0, 0.0, -0.25),
+                            ..default()
+                        },
+                        SpawnGlow,
+                    ));
+                });
         }
     }
 }
 
-fn end_game(
-    tiles: Query<(&Position, &Points)>,
+/// Marks the tile spawned by the last move while its glow fades.
+#[derive(Component)]
+struct JustSpawned(Timer);
+
+/// Slightly larger sprite behind a `JustSpawned` tile.
+#[derive(Component)]
+struct SpawnGlow;
+
+const SPAWN_GLOW_SECS: f32 = 0.6;
+const SPAWN_GLOW_ALPHA: f32 = 0.5;
+/// How much wider than the tile the glow is, over both sides.
+const SPAWN_GLOW_WIDTH: f32 = 12.0;
+
+fn fade_spawn_glow(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut tiles: Query<(Entity, &mut JustSpawned, &Children)>,
+    mut glows: Query<&mut Sprite, With<SpawnGlow>>,
+) {
+    for (entity, mut just_spawned, children) in tiles.iter_mut() {
+        just_spawned.0.tick(time.delta());
+
+        for child in children.iter() {
+            let Ok(mut sprite) = glows.get_mut(*child) else {
+                continue;
+            };
+
+            if just_spawned.0.finished() {
+                commands.entity(*child).despawn_recursive();
+            } else {
+                sprite
+                    .color
+                    .set_a(SPAWN_GLOW_ALPHA * just_spawned.0.percent_left());
+            }
+        }
+
+        if just_spawned.0.finished() {
+            commands.entity(entity).remove::<JustSpawned>();
+        }
+    }
+}
+
+fn win_check(
+    changed: Query<(), Changed<Points>>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
     query_board: Query<&Board>,
+    game: Res<Game>,
     mut run_state: ResMut<NextState<GameState>>,
 ) {
-    let board = query_board.single();
+    if game.keep_playing || changed.is_empty() {
+        return;
+    }
 
-    let max_tiles = 16;
+    if query_board
+        .iter()
+        .any(|board| board.grid(tiles.iter()).has_won(game.win_target))
+    {
+        run_state.set(GameState::Won);
+    }
+}
 
-    if tiles.iter().len() == max_tiles {
-        let map: HashMap<&Position, &Points> = tiles.iter().collect();
-        let neighbor_points = [(-1, 0), (0, 1), (1, 0), (0, -1)];
-        let board_range: Range<i8> = 0..(board.size as i8);
+fn continue_playing(
+    input: Res<Input<KeyCode>>,
+    buttons: Query<&Interaction, (Changed<Interaction>, With<ui::ContinueButton>)>,
+    mut game: ResMut<Game>,
+    mut resume: ResMut<Resume>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    let clicked = buttons
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked);
 
-        let has_move = tiles.iter().any(|(Position { x, y }, value)| {
-            neighbor_points
-                .iter()
-                .filter_map(|(x2, y2)| {
-                    let new_x = *x as i8 - x2;
-                    let new_y = *y as i8 - y2;
+    if clicked || input.just_pressed(KeyCode::Return) {
+        game.keep_playing = true;
+        resume.0 = true;
+        run_state.set(GameState::Playing);
+    }
+}
+
+fn toggle_pause(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<keybindings::KeyBindings>,
+    state: Res<State<GameState>>,
+    mut resume: ResMut<Resume>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    if !input.any_just_pressed(bindings.pause.iter().copied()) {
+        return;
+    }
 
-                    if !board_range.contains(&new_x) || !board_range.contains(&new_y) {
-                        return None;
-                    };
+    match state.0 {
+        GameState::Playing => run_state.set(GameState::Paused),
+        GameState::Paused => {
+            resume.0 = true;
+            run_state.set(GameState::Playing);
+        }
+        GameState::Menu
+        | GameState::Won
+        | GameState::GameOver
+        | GameState::ConfirmQuit
+        | GameState::Settings
+        | GameState::Versus => {}
+    }
+}
 
-                    map.get(&Position {
-                        x: new_x.try_into().unwrap(),
-                        y: new_y.try_into().unwrap(),
-                    })
-                })
-                .any(|&v| v == value)
+/// Starts over straight away. Re-entering `Playing` runs `game_reset` and
+/// `spawn_tiles`, which clear the tiles, score, moves and timer.
+fn restart(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<keybindings::KeyBindings>,
+    state: Res<State<GameState>>,
+    mut resume: ResMut<Resume>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    if !input.any_just_pressed(bindings.restart.iter().copied()) {
+        return;
+    }
+
+    match state.0 {
+        GameState::Playing | GameState::Paused | GameState::Won | GameState::GameOver => {
+            resume.0 = false;
+            run_state.set(GameState::Playing);
+        }
+        GameState::Menu | GameState::ConfirmQuit | GameState::Settings | GameState::Versus => {}
+    }
+}
+
+/// How long "No moves!" shows before the game over overlay, set with
+/// `--toast-ms`.
+#[derive(Resource)]
+struct ToastConfig {
+    no_moves: Duration,
+}
+
+impl Default for ToastConfig {
+    fn default() -> Self {
+        Self {
+            no_moves: Duration::from_millis(800),
+        }
+    }
+}
+
+/// "No moves!" banner, the game ends when its timer runs out.
+#[derive(Component)]
+struct NoMovesToast(Timer);
+
+/// Time running out ends the game right away. A stuck board first shows the
+/// "No moves!" toast, which `finish_no_moves_toast` turns into the game over,
+/// and an undo meanwhile takes the toast down again.
+#[allow(clippy::too_many_arguments)]
+fn end_game(
+    mut commands: Commands,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    query_board: Query<&Board>,
+    toasts: Query<Entity, With<NoMovesToast>>,
+    font_spec: Res<FontSpec>,
+    toast_config: Res<ToastConfig>,
+    mode: Res<mode::GameMode>,
+    timer: Res<GameTimer>,
+    game: Res<Game>,
+    mut run_state: ResMut<NextState<GameState>>,
+    mut game_over_events: EventWriter<GameOverEvent>,
+) {
+    if mode.time_is_up(&timer) {
+        info!("time's up with a score of {}", game.score);
+        game_over_events.send(GameOverEvent::new(&game, &timer));
+        run_state.set(GameState::GameOver);
+        return;
+    }
+
+    // zen games reshuffle a stuck board instead, see mode::zen_reshuffle
+    if *mode == mode::GameMode::Zen {
+        return;
+    }
+
+    // the game goes on while any board has a move left, and only a full
+    // board can run out of them
+    let stuck = !query_board.is_empty()
+        && query_board.iter().all(|board| {
+            let grid = board.grid(tiles.iter());
+            grid.is_full() && !grid::has_any_move(&grid)
         });
 
-        if !has_move {
-            dbg!("game over!");
+    if !stuck {
+        for entity in toasts.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    } else if toasts.is_empty() {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    z_index: ZIndex::Global(10),
+                    ..default()
+                },
+                NoMovesToast(Timer::new(toast_config.no_moves, TimerMode::Once)),
+                // leaving `Playing` for any reason, restarts included, drops it
+                state_scoped::StateScoped(GameState::Playing),
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "No moves!",
+                    TextStyle {
+                        font: font_spec.family.clone(),
+                        font_size: 60.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            });
+    }
+}
+
+fn finish_no_moves_toast(
+    time: Res<Time>,
+    mut toasts: Query<&mut NoMovesToast>,
+    game: Res<Game>,
+    timer: Res<GameTimer>,
+    mut run_state: ResMut<NextState<GameState>>,
+    mut game_over_events: EventWriter<GameOverEvent>,
+) {
+    for mut toast in toasts.iter_mut() {
+        if toast.0.tick(time.delta()).just_finished() {
+            info!("game over with a score of {}", game.score);
+            game_over_events.send(GameOverEvent::new(&game, &timer));
             run_state.set(GameState::GameOver);
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn game_reset(
     mut commands: Commands,
     tiles: Query<Entity, With<Position>>,
     mut game: ResMut<Game>,
+    mut undo_stack: ResMut<UndoStack>,
+    mut history: ResMut<MoveHistory>,
+    seed_config: Res<SeedConfig>,
+    mut rng: ResMut<GameRng>,
+    mut recorder: ResMut<replay::ReplayRecorder>,
+    mut player: ResMut<replay::ReplayPlayer>,
+    mut timer: ResMut<GameTimer>,
+    mut mode: ResMut<mode::GameMode>,
+    mut daily: ResMut<daily::DailyChallenge>,
+    mut retry: ResMut<RetrySeed>,
+    undo_config: Res<UndoConfig>,
+    mut undo_budget: ResMut<UndoBudget>,
+    args: Res<cli::Args>,
 ) {
     for entity in tiles.iter() {
         commands.entity(entity).despawn_recursive();
     }
 
+    daily.date = None;
+    // a replay brings its own, which mustn't outlive it
+    (game.four_spawn_chance, game.scale_four_chance) = args.four_chance_rules();
+    let seed = match player.begin(&mut mode) {
+        Some(replay) => {
+            game.four_spawn_chance = replay.four_spawn_chance;
+            game.scale_four_chance = replay.scale_four_chance;
+            replay.seed
+        }
+        None if *mode == mode::GameMode::Daily => {
+            let date = daily::Date::today_utc();
+            daily.date = Some(date);
+            date.seed()
+        }
+        None if retry.0 => rng.seed,
+        None => seed_config.seed.unwrap_or_else(rand::random),
+    };
+    retry.0 = false;
+    *rng = GameRng::new(seed);
+    info!("new {} game with seed {}", mode.label(), rng.seed);
     game.score = 0;
+    game.keep_playing = false;
+    game.move_count = 0;
+    game.max_tile = 0;
+    game.combo = 0;
+    game.shuffles_left = power_ups::SHUFFLES_PER_GAME;
+    game.removals_left = power_ups::REMOVALS_PER_GAME;
+    undo_stack.0.clear();
+    undo_budget.remaining = undo_config.per_game;
+    history.0.clear();
+    recorder.clear();
+    timer.elapsed = Duration::ZERO;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A world with what `board_shift` and `undo` need. The best score is
+    /// out of reach so no move writes the scores file.
+    fn shift_world() -> World {
+        let mut world = World::new();
+        world.insert_resource(Game {
+            score: 0,
+            best_score: u32::MAX,
+            four_spawn_chance: 0.1,
+            scale_four_chance: false,
+            win_target: 2048,
+            keep_playing: false,
+            move_count: 0,
+            max_tile: 0,
+            combo: 0,
+            shuffles_left: 0,
+            removals_left: 0,
+        });
+        world.insert_resource(GameRng::new(1));
+        world.insert_resource(AnimationConfig::default());
+        world.insert_resource(FontSpec {
+            family: Handle::default(),
+        });
+        world.insert_resource(keybindings::KeyBindings::default());
+        world.init_resource::<Input<KeyCode>>();
+        world.init_resource::<colors::Theme>();
+        world.init_resource::<colors::ColorblindMode>();
+        world.init_resource::<UndoStack>();
+        world.init_resource::<UndoBudget>();
+        world.init_resource::<MoveHistory>();
+        // a lock that was never set still has to be ticked once to open
+        let mut input_lock = InputLock::default();
+        input_lock.tick(Duration::ZERO);
+        world.insert_resource(input_lock);
+        world.init_resource::<replay::ReplayRecorder>();
+        world.init_resource::<mode::GameMode>();
+        world.init_resource::<Events<ShiftEvent>>();
+        world.init_resource::<Events<NewTileEvent>>();
+        world.init_resource::<Events<MergeEvent>>();
+        world.init_resource::<Events<BumpEvent>>();
+        world
+    }
+
+    /// Spawns a board with `tiles`, each `(x, y, value)`, on it.
+    fn spawn_board(world: &mut World, id: u8, tiles: &[(u8, u8, u32)]) {
+        let id = BoardId(id);
+        world.spawn((
+            Board {
+                id,
+                ..Board::new(4, 4, LAYOUTS[0])
+            },
+            id,
+        ));
+        for (x, y, value) in tiles {
+            world.spawn((Position { x: *x, y: *y }, Points { value: *value }, id));
+        }
+    }
+
+    /// The tiles on board `id`, as `(x, y, value)` sorted by cell.
+    fn board_tiles(world: &mut World, id: u8) -> Vec<(u8, u8, u32)> {
+        let mut tiles = world
+            .query::<(&Position, &Points, &BoardId)>()
+            .iter(world)
+            .filter(|(.., board_id)| board_id.0 == id)
+            .map(|(pos, points, _)| (pos.x, pos.y, points.value))
+            .collect::<Vec<_>>();
+        tiles.sort_unstable();
+        tiles
+    }
+
+    fn run<M>(world: &mut World, system: impl IntoSystemConfig<M>) {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system);
+        schedule.run(world);
+    }
+
+    fn shift(world: &mut World, direction: BoardShift) {
+        world
+            .resource_mut::<Events<ShiftEvent>>()
+            .send(ShiftEvent(direction));
+        run(world, board_shift);
+    }
+
+    fn sent<E: Event>(world: &World) -> Vec<&E> {
+        world
+            .resource::<Events<E>>()
+            .iter_current_update_events()
+            .collect()
+    }
+
+    #[test]
+    fn boards_shift_without_merging_into_each_other() {
+        let mut world = shift_world();
+        spawn_board(&mut world, 0, &[(1, 0, 2), (3, 0, 2)]);
+        // the same cells as board 0 but a different value, so a merge
+        // across boards would show
+        spawn_board(&mut world, 1, &[(1, 0, 4), (2, 0, 8)]);
+
+        shift(&mut world, BoardShift::Left);
+
+        assert_eq!(board_tiles(&mut world, 0), [(0, 0, 4)]);
+        assert_eq!(board_tiles(&mut world, 1), [(0, 0, 4), (1, 0, 8)]);
+        assert_eq!(world.resource::<Game>().score, 4);
+        let merges = sent::<MergeEvent>(&world);
+        assert_eq!(merges.len(), 1);
+        assert_eq!(merges[0].board, BoardId(0));
+        let mut new_tiles = sent::<NewTileEvent>(&world)
+            .iter()
+            .map(|event| event.0 .0)
+            .collect::<Vec<_>>();
+        new_tiles.sort_unstable();
+        assert_eq!(new_tiles, [0, 1]);
+    }
+
+    #[test]
+    fn only_the_boards_that_moved_get_a_tile() {
+        let mut world = shift_world();
+        spawn_board(&mut world, 0, &[(2, 1, 2)]);
+        spawn_board(&mut world, 1, &[(0, 1, 2)]);
+
+        shift(&mut world, BoardShift::Left);
+
+        assert_eq!(board_tiles(&mut world, 0), [(0, 1, 2)]);
+        assert_eq!(board_tiles(&mut world, 1), [(0, 1, 2)]);
+        let new_tiles = sent::<NewTileEvent>(&world);
+        assert_eq!(new_tiles.len(), 1);
+        assert_eq!(new_tiles[0].0, BoardId(0));
+        assert!(sent::<BumpEvent>(&world).is_empty());
+    }
+
+    #[test]
+    fn a_move_nothing_can_take_bumps_every_board() {
+        let mut world = shift_world();
+        spawn_board(&mut world, 0, &[(0, 0, 2)]);
+        spawn_board(&mut world, 1, &[(0, 3, 4)]);
+
+        shift(&mut world, BoardShift::Left);
+
+        let mut bumped = sent::<BumpEvent>(&world)
+            .iter()
+            .map(|event| (event.board.0, event.dir))
+            .collect::<Vec<_>>();
+        bumped.sort_unstable_by_key(|(board, _)| *board);
+        assert_eq!(bumped, [(0, BoardShift::Left), (1, BoardShift::Left)]);
+        assert!(sent::<NewTileEvent>(&world).is_empty());
+        assert_eq!(world.resource::<Game>().move_count, 0);
+    }
+
+    #[test]
+    fn a_single_board_shifts_like_the_grid() {
+        let tiles = [
+            (0, 0, 2),
+            (1, 0, 2),
+            (2, 0, 4),
+            (0, 2, 8),
+            (0, 3, 8),
+            (3, 3, 16),
+        ];
+        for direction in [
+            BoardShift::Left,
+            BoardShift::Right,
+            BoardShift::Up,
+            BoardShift::Down,
+        ] {
+            let mut world = shift_world();
+            spawn_board(&mut world, 0, &tiles);
+            let board = Board::new(4, 4, LAYOUTS[0]);
+            let grid = grid::Grid::from_tiles(
+                board.width,
+                board.height,
+                tiles
+                    .iter()
+                    .map(|(x, y, value)| (Position { x: *x, y: *y }, *value)),
+            );
+            let (expected, gain, _) = grid::shift_grid(&grid, direction);
+
+            shift(&mut world, direction);
+
+            let shifted = board_tiles(&mut world, 0);
+            let grid = grid::Grid::from_tiles(
+                board.width,
+                board.height,
+                shifted
+                    .iter()
+                    .map(|(x, y, value)| (Position { x: *x, y: *y }, *value)),
+            );
+            assert_eq!(grid.rows(), expected.rows(), "{direction:?}");
+            assert_eq!(world.resource::<Game>().score, gain, "{direction:?}");
+        }
+    }
+
+    #[test]
+    fn a_board_with_a_game_of_its_own_spawns_from_its_own_rng() {
+        let mut world = shift_world();
+        world.init_resource::<SpawnBias>();
+        spawn_board(&mut world, 0, &[(0, 0, 2)]);
+        spawn_board(&mut world, 1, &[(0, 0, 2)]);
+        world.spawn((
+            BoardId(1),
+            BoardGame {
+                score: 0,
+                rng: ChaCha12Rng::seed_from_u64(7),
+            },
+        ));
+        let shared_rng = world.resource::<GameRng>().rng.clone();
+
+        world
+            .resource_mut::<Events<NewTileEvent>>()
+            .send(NewTileEvent(BoardId(1)));
+        run(&mut world, new_tile_handler);
+
+        assert_eq!(board_tiles(&mut world, 0).len(), 1);
+        assert_eq!(board_tiles(&mut world, 1).len(), 2);
+        assert!(world.resource::<GameRng>().rng == shared_rng);
+        let mut board_games = world.query::<&BoardGame>();
+        assert!(board_games.single(&world).rng != ChaCha12Rng::seed_from_u64(7));
+        // the shared game keeps to its own tiles
+        assert_eq!(world.resource::<Game>().max_tile, 0);
+    }
+
+    #[test]
+    fn undo_puts_tiles_back_on_their_own_boards() {
+        let mut world = shift_world();
+        let before = [
+            vec![(0, 0, 2), (3, 0, 2)],
+            vec![(0, 3, 8), (1, 2, 4), (3, 2, 4)],
+        ];
+        spawn_board(&mut world, 0, &before[0]);
+        spawn_board(&mut world, 1, &before[1]);
+
+        shift(&mut world, BoardShift::Right);
+        assert_ne!(board_tiles(&mut world, 1), before[1]);
+
+        world.resource_mut::<Input<KeyCode>>().press(KeyCode::U);
+        run(&mut world, undo);
+
+        assert_eq!(board_tiles(&mut world, 0), before[0]);
+        assert_eq!(board_tiles(&mut world, 1), before[1]);
+        assert_eq!(world.resource::<Game>().score, 0);
+    }
+
+    /// Time from a move until the lock lets the next one through, ticking
+    /// at `hz` frames a second.
+    fn unlock_after(config: &AnimationConfig, hz: u64) -> Duration {
+        let frame = Duration::from_nanos(1_000_000_000 / hz);
+        let mut lock = InputLock::default();
+        lock.lock_for(config);
+
+        let mut elapsed = Duration::ZERO;
+        while lock.is_locked() {
+            lock.tick(frame);
+            elapsed += frame;
+        }
+        elapsed
+    }
+
+    #[test]
+    fn input_lock_lasts_the_slide_at_any_frame_rate() {
+        for slide_ms in SLIDE_SPEEDS_MS {
+            let config = AnimationConfig {
+                slide_ms,
+                ..default()
+            };
+            let slide = Duration::from_millis(slide_ms);
+
+            for hz in [30, 60, 240] {
+                let frame = Duration::from_nanos(1_000_000_000 / hz);
+                let elapsed = unlock_after(&config, hz);
+                // never early, and never more than the frame it ends in late
+                assert!(elapsed >= slide, "{slide_ms}ms unlocked early at {hz} Hz");
+                assert!(
+                    elapsed < slide + frame,
+                    "{slide_ms}ms took {elapsed:?} at {hz} Hz"
+                );
+            }
+        }
+    }
 }
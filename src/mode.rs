@@ -0,0 +1,205 @@
+use std::time::Duration;
+
+use b2048::{has_any_move, Grid};
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, BoardId, GameRng, GameState, GameTimer, Points, Position};
+
+/// Length of a `GameMode::TimeAttack` game.
+pub const TIME_ATTACK_LIMIT: Duration = Duration::from_secs(120);
+/// How long the game over screen stays up before `GameMode::Endless` restarts.
+const ENDLESS_RESTART_DELAY: f32 = 2.0;
+/// Shuffles `GameMode::Zen` tries before giving up and removing a tile.
+const ZEN_SHUFFLE_ATTEMPTS: usize = 10;
+
+pub struct GameModePlugin;
+
+impl Plugin for GameModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<EndlessRestart>()
+            .add_system(cycle_game_mode)
+            .add_system(reset_endless_restart.in_schedule(OnEnter(GameState::GameOver)))
+            .add_system(
+                endless_restart
+                    .run_if(resource_equals(GameMode::Endless))
+                    .in_set(OnUpdate(GameState::GameOver)),
+            )
+            .add_system(
+                zen_reshuffle
+                    .run_if(resource_equals(GameMode::Zen))
+                    .in_set(OnUpdate(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource, Serialize, Deserialize)]
+pub enum GameMode {
+    /// The game ends when no move is left.
+    #[default]
+    Classic,
+    /// A new game starts by itself after every game over.
+    Endless,
+    /// Score as much as possible within `TIME_ATTACK_LIMIT`.
+    TimeAttack,
+    /// Classic rules, seeded from the date so everyone gets the same tiles.
+    Daily,
+    /// No game over: a stuck board is shuffled until a move opens up.
+    Zen,
+    /// A sandbox without random tiles: clicking a cell places or grows a
+    /// tile, see `practice`. These games don't count towards any score.
+    Practice,
+}
+
+impl GameMode {
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Classic => GameMode::Endless,
+            GameMode::Endless => GameMode::TimeAttack,
+            GameMode::TimeAttack => GameMode::Daily,
+            GameMode::Daily => GameMode::Zen,
+            GameMode::Zen => GameMode::Practice,
+            GameMode::Practice => GameMode::Classic,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Classic => "Classic",
+            GameMode::Endless => "Endless",
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Daily => "Daily",
+            GameMode::Zen => "Zen",
+            GameMode::Practice => "Practice",
+        }
+    }
+
+    /// Whether a `TimeAttack` game has used up its time.
+    pub fn time_is_up(self, timer: &GameTimer) -> bool {
+        self == GameMode::TimeAttack && timer.elapsed >= TIME_ATTACK_LIMIT
+    }
+}
+
+pub fn is_practice(mode: Res<GameMode>) -> bool {
+    *mode == GameMode::Practice
+}
+
+pub fn not_practice(mode: Res<GameMode>) -> bool {
+    *mode != GameMode::Practice
+}
+
+#[derive(Default, Resource)]
+struct EndlessRestart(Timer);
+
+/// G switches mode and starts a new game in it. The menu has its own button.
+fn cycle_game_mode(
+    input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut mode: ResMut<GameMode>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(KeyCode::G)
+        || matches!(
+            state.0,
+            GameState::Menu | GameState::ConfirmQuit | GameState::Settings | GameState::Versus
+        )
+    {
+        return;
+    }
+
+    *mode = mode.next();
+    info!("game mode: {}", mode.label());
+    run_state.set(GameState::Playing);
+}
+
+fn reset_endless_restart(mut restart: ResMut<EndlessRestart>) {
+    restart.0 = Timer::from_seconds(ENDLESS_RESTART_DELAY, TimerMode::Once);
+}
+
+fn endless_restart(
+    time: Res<Time>,
+    mut restart: ResMut<EndlessRestart>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    if restart.0.tick(time.delta()).just_finished() {
+        run_state.set(GameState::Playing);
+    }
+}
+
+/// Shuffles the tiles of a stuck board with `GameRng`, so replays shuffle the
+/// same way. Boards go in id order and tiles are sorted by cell first, as
+/// query order isn't stable. If no shuffle frees a move, the lowest tile is
+/// removed instead.
+fn zen_reshuffle(
+    mut commands: Commands,
+    query_board: Query<&Board>,
+    mut tiles: Query<(Entity, &mut Position, &Points, &BoardId)>,
+    mut rng: ResMut<GameRng>,
+) {
+    for board in query_board.iter().sorted_by_key(|board| board.id.0) {
+        reshuffle_board(&mut commands, board, &mut tiles, &mut rng);
+    }
+}
+
+fn reshuffle_board(
+    commands: &mut Commands,
+    board: &Board,
+    tiles: &mut Query<(Entity, &mut Position, &Points, &BoardId)>,
+    rng: &mut GameRng,
+) {
+    let grid_of = |cells: &[(Position, u32)]| {
+        Grid::from_tiles(board.width, board.height, cells.iter().copied())
+    };
+
+    let mut cells = tiles
+        .iter()
+        .filter(|(.., id)| **id == board.id)
+        .map(|(_, pos, points, _)| (*pos, points.value))
+        .collect::<Vec<_>>();
+    let grid = grid_of(&cells);
+    if !grid.is_full() || has_any_move(&grid) {
+        return;
+    }
+    cells.sort_by_key(|(pos, _)| (pos.x, pos.y));
+
+    let positions = cells.iter().map(|(pos, _)| *pos).collect::<Vec<_>>();
+    for _ in 0..ZEN_SHUFFLE_ATTEMPTS {
+        let mut shuffled = positions.clone();
+        shuffled.shuffle(&mut rng.rng);
+        let moved = shuffled
+            .iter()
+            .zip(&cells)
+            .map(|(to, (_, value))| (*to, *value))
+            .collect::<Vec<_>>();
+
+        if has_any_move(&grid_of(&moved)) {
+            info!("zen: shuffled a stuck board");
+            for (.., mut pos, _, id) in tiles.iter_mut() {
+                if *id != board.id {
+                    continue;
+                }
+                let index = positions.iter().position(|from| *from == *pos);
+                if let Some(index) = index {
+                    // left alone when it lands where it was, so it doesn't re-ease
+                    if *pos != shuffled[index] {
+                        *pos = shuffled[index];
+                    }
+                }
+            }
+            return;
+        }
+    }
+
+    let lowest = tiles
+        .iter()
+        .filter(|(.., id)| **id == board.id)
+        .min_by_key(|(_, pos, points, _)| (points.value, pos.x, pos.y))
+        .map(|(entity, ..)| entity);
+    if let Some(entity) = lowest {
+        info!("zen: no shuffle helped, removing the lowest tile");
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,212 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{colors, state_scoped::StateScoped, Board, GameState, MergeEvent};
+
+/// Bursts of small squares flying out of merged tiles.
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ParticlesEnabled>()
+            .add_system(toggle_particles)
+            .add_system(spawn_merge_particles.in_set(OnUpdate(GameState::Playing)))
+            .add_system(spawn_confetti.in_schedule(OnEnter(GameState::Won)))
+            .add_system(animate_particles);
+    }
+}
+
+/// Turns the merge bursts off on machines that struggle with them, F7
+/// toggles it.
+#[derive(Resource)]
+pub struct ParticlesEnabled(pub bool);
+
+impl Default for ParticlesEnabled {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+const PARTICLES_PER_MERGE: usize = 8;
+/// Upper bound of live particles, so a move merging many tiles at once
+/// doesn't flood the board.
+const MAX_PARTICLES: usize = 64;
+const PARTICLE_SIZE: f32 = 8.0;
+const PARTICLE_LIFETIME: f32 = 0.4;
+/// Speed in pixels per second, the fast half of a burst gets twice as much.
+const PARTICLE_SPEED: f32 = 120.0;
+
+const CONFETTI_COUNT: usize = 120;
+const CONFETTI_SIZE: Vec2 = Vec2::new(6.0, 10.0);
+/// The longest a piece of confetti stays, each one gets up to half of it
+/// shaved off so they don't all vanish together.
+const CONFETTI_LIFETIME: f32 = 2.5;
+/// Falling speed in pixels per second.
+const CONFETTI_SPEED: f32 = 160.0;
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec2,
+    timer: Timer,
+}
+
+fn toggle_particles(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut enabled: ResMut<ParticlesEnabled>,
+    particles: Query<Entity, With<Particle>>,
+) {
+    if !input.just_pressed(KeyCode::F7) {
+        return;
+    }
+
+    enabled.0 = !enabled.0;
+    if !enabled.0 {
+        for entity in particles.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Particles are children of the board so they follow it when it bumps.
+fn spawn_merge_particles(
+    mut commands: Commands,
+    mut merge_events: EventReader<MergeEvent>,
+    enabled: Res<ParticlesEnabled>,
+    boards: Query<(Entity, &Board)>,
+    particles: Query<(), With<Particle>>,
+    tile_colors: colors::TileColors,
+) {
+    if !enabled.0 {
+        merge_events.clear();
+        return;
+    }
+
+    let mut budget = MAX_PARTICLES.saturating_sub(particles.iter().count());
+
+    for event in merge_events.iter() {
+        let count = PARTICLES_PER_MERGE.min(budget);
+        if count == 0 {
+            break;
+        }
+        let Some((board_entity, board)) = boards.iter().find(|(_, board)| board.id == event.board)
+        else {
+            continue;
+        };
+        budget -= count;
+
+        let origin = Vec2::new(
+            board.physical_x(event.position.x),
+            board.physical_y(event.position.y),
+        );
+        let color = tile_colors.tile(event.points);
+
+        commands.entity(board_entity).with_children(|builder| {
+            for index in 0..count {
+                let angle = index as f32 / count as f32 * TAU;
+                let speed = PARTICLE_SPEED * if index % 2 == 0 { 1.0 } else { 2.0 };
+
+                builder.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(Vec2::splat(PARTICLE_SIZE)),
+                            ..default()
+                        },
+                        // above the tiles
+                        transform: Transform::from_translation(origin.extend(2.5)),
+                        ..default()
+                    },
+                    Particle {
+                        velocity: Vec2::from_angle(angle) * speed,
+                        timer: Timer::from_seconds(PARTICLE_LIFETIME, TimerMode::Once),
+                    },
+                ));
+            }
+        });
+    }
+}
+
+fn animate_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in particles.iter_mut() {
+        particle.timer.tick(time.delta());
+
+        if particle.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        transform.translation += (particle.velocity * time.delta_seconds()).extend(0.0);
+        sprite.color.set_a(particle.timer.percent_left());
+    }
+}
+
+/// Confetti raining down every board for the win overlay, in the colors
+/// of the tiles up to 2048. Drawn from the thread rng rather than `GameRng`
+/// so replays aren't thrown off.
+fn spawn_confetti(
+    mut commands: Commands,
+    enabled: Res<ParticlesEnabled>,
+    boards: Query<(Entity, &Board)>,
+    tile_colors: colors::TileColors,
+) {
+    if !enabled.0 {
+        return;
+    }
+
+    let mut rng = thread_rng();
+    for (board_entity, board) in boards.iter() {
+        spawn_board_confetti(&mut commands, board_entity, board, &tile_colors, &mut rng);
+    }
+}
+
+fn spawn_board_confetti(
+    commands: &mut Commands,
+    board_entity: Entity,
+    board: &Board,
+    tile_colors: &colors::TileColors,
+    rng: &mut impl Rng,
+) {
+    let half = board.physical_size / 2.0;
+
+    commands.entity(board_entity).with_children(|builder| {
+        for _ in 0..CONFETTI_COUNT {
+            let origin = Vec2::new(
+                rng.gen_range(-half.x..=half.x),
+                half.y + rng.gen_range(0.0..half.y),
+            );
+            let velocity = Vec2::new(
+                rng.gen_range(-0.3..=0.3) * CONFETTI_SPEED,
+                -rng.gen_range(0.5..=1.0) * CONFETTI_SPEED,
+            );
+            let color = tile_colors.tile(1 << rng.gen_range(1..=11));
+
+            builder.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color,
+                        custom_size: Some(CONFETTI_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(origin.extend(2.5))
+                        .with_rotation(Quat::from_rotation_z(rng.gen_range(0.0..TAU))),
+                    ..default()
+                },
+                Particle {
+                    velocity,
+                    timer: Timer::from_seconds(
+                        CONFETTI_LIFETIME * rng.gen_range(0.5..=1.0),
+                        TimerMode::Once,
+                    ),
+                },
+                StateScoped(GameState::Won),
+            ));
+        }
+    });
+}
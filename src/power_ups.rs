@@ -0,0 +1,172 @@
+use bevy::prelude::*;
+use itertools::Itertools;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    replay, AnimationConfig, Board, BoardId, Game, GameRng, GameState, InputLock, Merging, Points,
+    Position,
+};
+
+/// Shuffles each game starts with.
+pub const SHUFFLES_PER_GAME: u32 = 2;
+/// Lowest tile removals each game starts with.
+pub const REMOVALS_PER_GAME: u32 = 1;
+
+/// Limited-use actions on top of the regular moves, refilled by
+/// `game_reset`. They are recorded in replays like moves, so a replay
+/// changes the board, and draws from `GameRng`, at the same points.
+pub struct PowerUpsPlugin;
+
+impl Plugin for PowerUpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PowerUpEvent>().add_systems(
+            (
+                power_up_input.run_if(replay::not_replaying),
+                use_power_up
+                    .after(power_up_input)
+                    .before(crate::board_shift),
+            )
+                .in_set(OnUpdate(GameState::Playing)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PowerUp {
+    /// Moves every tile to another occupied cell, picked at random.
+    Shuffle,
+    /// Takes the lowest tile off the board.
+    RemoveLowest,
+}
+
+/// A request to use a power-up, from the keyboard or a replay.
+pub struct PowerUpEvent(pub PowerUp);
+
+/// X shuffles, E erases the lowest tile.
+fn power_up_input(input: Res<Input<KeyCode>>, mut power_up_events: EventWriter<PowerUpEvent>) {
+    if input.just_pressed(KeyCode::X) {
+        power_up_events.send(PowerUpEvent(PowerUp::Shuffle));
+    }
+    if input.just_pressed(KeyCode::E) {
+        power_up_events.send(PowerUpEvent(PowerUp::RemoveLowest));
+    }
+}
+
+/// Ignored halfway through a slide, like moves, and when none are left.
+/// Every board gets the power-up, in id order so replays use it the same
+/// way, and a use is spent if any of them could take it.
+/// Undo doesn't give a use back: the snapshot it restores was taken at the
+/// last move, before the power-up.
+#[allow(clippy::too_many_arguments)]
+fn use_power_up(
+    mut commands: Commands,
+    mut power_up_events: EventReader<PowerUpEvent>,
+    boards: Query<&Board>,
+    mut tiles: PowerUpTiles,
+    mut game: ResMut<Game>,
+    mut rng: ResMut<GameRng>,
+    mut recorder: ResMut<replay::ReplayRecorder>,
+    mut input_lock: ResMut<InputLock>,
+    animation: Res<AnimationConfig>,
+) {
+    if input_lock.is_locked() {
+        power_up_events.clear();
+        return;
+    }
+    let Some(PowerUpEvent(power_up)) = power_up_events.iter().next() else {
+        return;
+    };
+
+    let left = match power_up {
+        PowerUp::Shuffle => game.shuffles_left,
+        PowerUp::RemoveLowest => game.removals_left,
+    };
+    if left == 0 {
+        return;
+    }
+
+    let mut used = false;
+    for board in boards.iter().sorted_by_key(|board| board.id.0) {
+        used |= match power_up {
+            PowerUp::Shuffle => shuffle(&mut tiles, board.id, &mut rng),
+            PowerUp::RemoveLowest => remove_lowest(&mut commands, &tiles, board.id),
+        };
+    }
+    if !used {
+        return;
+    }
+
+    match power_up {
+        PowerUp::Shuffle => game.shuffles_left -= 1,
+        PowerUp::RemoveLowest => game.removals_left -= 1,
+    }
+    // lets the tiles slide to their new cells before the next move
+    input_lock.lock_for(&animation);
+    recorder.record_power_up(*power_up);
+}
+
+type PowerUpTiles<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Position,
+        &'static Points,
+        &'static BoardId,
+    ),
+>;
+
+/// Sorts the cells first, as query order isn't stable and replays have to
+/// shuffle the same way. Returns false when there is nothing to shuffle.
+fn shuffle(tiles: &mut PowerUpTiles, board_id: BoardId, rng: &mut GameRng) -> bool {
+    let mut positions = tiles
+        .iter()
+        .filter(|(.., id)| **id == board_id)
+        .map(|(_, pos, ..)| *pos)
+        .collect::<Vec<_>>();
+    if positions.len() < 2 {
+        return false;
+    }
+    positions.sort_by_key(|pos| (pos.x, pos.y));
+
+    let mut shuffled = positions.clone();
+    shuffled.shuffle(&mut rng.rng);
+
+    for (_, mut pos, _, id) in tiles.iter_mut() {
+        if *id != board_id {
+            continue;
+        }
+        let index = positions.binary_search_by_key(&(pos.x, pos.y), |from| (from.x, from.y));
+        if let Ok(index) = index {
+            // left alone when it lands where it was, so it doesn't re-ease
+            if *pos != shuffled[index] {
+                *pos = shuffled[index];
+            }
+        }
+    }
+    info!("shuffled {} tiles", positions.len());
+    true
+}
+
+/// Ties go to the tile in the lowest column, then row, so replays remove
+/// the same one. The board keeps at least one tile.
+fn remove_lowest(commands: &mut Commands, tiles: &PowerUpTiles, board_id: BoardId) -> bool {
+    let board_tiles = || tiles.iter().filter(|(.., id)| **id == board_id);
+    if board_tiles().count() < 2 {
+        return false;
+    }
+    let Some((entity, pos, points, _)) =
+        board_tiles().min_by_key(|(_, pos, points, _)| (points.value, pos.x, pos.y))
+    else {
+        return false;
+    };
+
+    // shrinks away in place, like a tile merging into its own cell
+    commands
+        .entity(entity)
+        .remove::<(Position, Points)>()
+        .insert(Merging { into: *pos });
+    info!("removed the lowest tile, worth {}", points.value);
+    true
+}
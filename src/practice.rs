@@ -0,0 +1,89 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    colors, cursor_cell, mode, replay, spawn_tile, Board, BoardId, FontSpec, Game, GameState,
+    Points, Position,
+};
+
+/// Free tile placement for `GameMode::Practice`.
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(
+            edit_practice_tile
+                .run_if(mode::is_practice)
+                .run_if(replay::not_replaying)
+                .in_set(OnUpdate(GameState::Playing)),
+        );
+    }
+}
+
+/// Clicking a tile past this value clears its cell.
+const MAX_PRACTICE_VALUE: u32 = 2048;
+
+/// A left click places a 2 on an empty cell and doubles a tile, a right
+/// click clears the cell. Only clicks pressed and released on the same cell
+/// count, so a swipe starting on a tile leaves it alone.
+#[allow(clippy::too_many_arguments)]
+fn edit_practice_tile(
+    mut commands: Commands,
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    boards: Query<(Entity, &Board, &GlobalTransform)>,
+    mut tiles: Query<(Entity, &Position, &mut Points, &BoardId)>,
+    font_spec: Res<FontSpec>,
+    tile_colors: colors::TileColors,
+    mut game: ResMut<Game>,
+    mut pressed: Local<Option<(BoardId, Position)>>,
+) {
+    // the board under the cursor, and the cell of it
+    let hovered = boards
+        .iter()
+        .find_map(|(board_entity, board, board_transform)| {
+            let cell = cursor_cell(&windows, &cameras, board, board_transform)?;
+            Some((board_entity, board, cell))
+        });
+    let cell = hovered.map(|(_, board, cell)| (board.id, cell));
+
+    if mouse.any_just_pressed([MouseButton::Left, MouseButton::Right]) {
+        *pressed = cell;
+    }
+
+    let clear = mouse.just_released(MouseButton::Right);
+    if !(clear || mouse.just_released(MouseButton::Left)) {
+        return;
+    }
+    let Some((board_entity, board, pos)) =
+        hovered.filter(|(_, board, cell)| pressed.take() == Some((board.id, *cell)))
+    else {
+        return;
+    };
+
+    let tile = tiles
+        .iter_mut()
+        .find(|(_, tile_pos, _, id)| **tile_pos == pos && **id == board.id);
+    match tile {
+        Some((entity, _, points, _)) if clear || points.value >= MAX_PRACTICE_VALUE => {
+            commands.entity(entity).despawn_recursive();
+        }
+        Some((_, _, mut points, _)) => {
+            points.value *= 2;
+            game.max_tile = game.max_tile.max(points.value);
+        }
+        None if !clear => {
+            spawn_tile(
+                &mut commands,
+                board_entity,
+                board,
+                &font_spec,
+                &tile_colors,
+                pos,
+                2,
+            );
+            game.max_tile = game.max_tile.max(2);
+        }
+        None => {}
+    }
+}
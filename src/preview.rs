@@ -0,0 +1,186 @@
+use b2048::grid::plan_shift;
+use bevy::prelude::*;
+
+use crate::{
+    colors::TileColors, keybindings::KeyBindings, replay, state_scoped::StateScoped,
+    tile_font_size, Board, BoardId, BoardShift, FontSpec, GameState, InputLock, Points, Position,
+    ShiftEvent,
+};
+
+/// Holding a direction shows faint ghosts of where the tiles would land,
+/// and the move only happens once the key is let go. Off by default as it
+/// changes how the keys feel, toggled with I.
+pub struct PreviewPlugin;
+
+impl Plugin for PreviewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PreviewMoves>()
+            .init_resource::<MovePreview>()
+            .add_system(toggle_preview)
+            .add_systems(
+                (
+                    preview_input
+                        .run_if(previewing)
+                        .run_if(replay::not_replaying)
+                        .after(crate::tick_input_lock)
+                        .before(crate::board_shift),
+                    spawn_ghosts.after(preview_input),
+                )
+                    .in_set(OnUpdate(GameState::Playing)),
+            )
+            .add_system(clear_preview.in_schedule(OnExit(GameState::Playing)));
+    }
+}
+
+#[derive(Default, Resource)]
+pub struct PreviewMoves(pub bool);
+
+#[derive(Default, Resource)]
+struct MovePreview {
+    /// The direction key held down, and the direction it previews.
+    held: Option<(KeyCode, BoardShift)>,
+    /// A direction let go of during a slide, sent once the slide is over
+    /// since `board_shift` drops moves sent during one.
+    released: Option<BoardShift>,
+}
+
+impl MovePreview {
+    /// The held direction, or else the one waiting to be sent.
+    fn direction(&self) -> Option<BoardShift> {
+        self.held.map(|(_, direction)| direction).or(self.released)
+    }
+}
+
+/// Translucent copy of a tile where the previewed move would put it.
+#[derive(Component)]
+struct Ghost;
+
+const GHOST_ALPHA: f32 = 0.35;
+
+pub fn previewing(preview: Res<PreviewMoves>) -> bool {
+    preview.0
+}
+
+pub fn not_previewing(preview: Res<PreviewMoves>) -> bool {
+    !preview.0
+}
+
+fn toggle_preview(
+    input: Res<Input<KeyCode>>,
+    mut preview: ResMut<PreviewMoves>,
+    mut move_preview: ResMut<MovePreview>,
+) {
+    if input.just_pressed(KeyCode::I) {
+        preview.0 = !preview.0;
+        *move_preview = MovePreview::default();
+    }
+}
+
+/// Takes over from `keyboard_input` while previewing: a press only picks
+/// the direction and the release moves, as soon as the last slide is over.
+/// There is no key repeat.
+fn preview_input(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    input_lock: Res<InputLock>,
+    mut preview: ResMut<MovePreview>,
+    mut shift_events: EventWriter<ShiftEvent>,
+) {
+    if let Some(pressed) = bindings.pressed_shift(input.get_just_pressed().copied()) {
+        preview.held = Some(pressed);
+    } else if let Some((key, direction)) = preview.held {
+        if input.just_released(key) {
+            preview.held = None;
+            preview.released = Some(direction);
+        } else if !input.pressed(key) {
+            // released while the window was in the background
+            preview.held = None;
+        }
+    }
+
+    if preview.released.is_some() && !input_lock.is_locked() {
+        if let Some(direction) = preview.released.take() {
+            shift_events.send(ShiftEvent(direction));
+        }
+    }
+}
+
+/// The ghosts go with the state, see `StateScoped`.
+fn clear_preview(mut preview: ResMut<MovePreview>) {
+    *preview = MovePreview::default();
+}
+
+/// Rebuilds the ghosts from the tiles whenever the previewed direction or
+/// the board changes. Only tiles that would move or merge get one.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn spawn_ghosts(
+    mut commands: Commands,
+    preview: Res<MovePreview>,
+    boards: Query<(Entity, &Board)>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    changed_tiles: Query<(), Or<(Changed<Position>, Changed<Points>)>>,
+    ghosts: Query<Entity, With<Ghost>>,
+    font_spec: Res<FontSpec>,
+    tile_colors: TileColors,
+) {
+    if !preview.is_changed() && changed_tiles.is_empty() {
+        return;
+    }
+
+    for entity in ghosts.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let Some(direction) = preview.direction() else {
+        return;
+    };
+
+    for (board_entity, board) in boards.iter() {
+        let shift = plan_shift(&board.grid(tiles.iter()), direction);
+
+        commands.entity(board_entity).with_children(|builder| {
+            for slide in shift
+                .slides
+                .iter()
+                .filter(|slide| slide.from != slide.to || slide.merged.is_some())
+            {
+                let (x, y) = slide.to;
+                builder
+                    .spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color: tile_colors.tile(slide.value).with_a(GHOST_ALPHA),
+                                custom_size: Some(board.tile_size()),
+                                ..default()
+                            },
+                            // over the tiles and their text, as ghosts often
+                            // land on a cell that is taken for now
+                            transform: Transform::from_xyz(
+                                board.physical_x(x),
+                                board.physical_y(y),
+                                2.5,
+                            ),
+                            ..default()
+                        },
+                        Ghost,
+                        StateScoped(GameState::Playing),
+                    ))
+                    .with_children(|builder| {
+                        builder.spawn(Text2dBundle {
+                            text: Text::from_section(
+                                slide.value.to_string(),
+                                TextStyle {
+                                    font: font_spec.family.clone(),
+                                    font_size: tile_font_size(slide.value, &board.layout),
+                                    color: tile_colors.text(slide.value).with_a(GHOST_ALPHA),
+                                },
+                            )
+                            .with_alignment(TextAlignment::Center),
+                            transform: Transform::from_xyz(0.0, 0.0, 0.1),
+                            ..default()
+                        });
+                    });
+            }
+        });
+    }
+}
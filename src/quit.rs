@@ -0,0 +1,73 @@
+use bevy::{app::AppExit, prelude::*, window::WindowCloseRequested};
+
+use crate::{GameState, Resume};
+
+pub struct QuitPlugin;
+
+impl Plugin for QuitPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<QuitPrompt>()
+            .add_system(request_quit)
+            .add_system(confirm_quit.in_set(OnUpdate(GameState::ConfirmQuit)));
+    }
+}
+
+/// State to go back to when the quit is cancelled.
+#[derive(Default, Resource)]
+struct QuitPrompt {
+    back_to: GameState,
+}
+
+/// Esc or closing the window asks first while a game is in progress, and
+/// quits straight away otherwise.
+fn request_quit(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    mut close_events: EventReader<WindowCloseRequested>,
+    state: Res<State<GameState>>,
+    mut prompt: ResMut<QuitPrompt>,
+    mut run_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let closed_windows = close_events
+        .iter()
+        .map(|event| event.window)
+        .collect::<Vec<_>>();
+    if closed_windows.is_empty() && !input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    match state.0 {
+        // Esc closes the settings, see settings::toggle_settings
+        GameState::Settings if closed_windows.is_empty() => {}
+        GameState::Playing | GameState::Paused | GameState::Won | GameState::Settings => {
+            prompt.back_to = state.0.clone();
+            run_state.set(GameState::ConfirmQuit);
+        }
+        // and leaves versus, see versus::leave_versus
+        GameState::Versus if closed_windows.is_empty() => {}
+        GameState::ConfirmQuit => {}
+        GameState::Menu | GameState::GameOver if closed_windows.is_empty() => exit.send(AppExit),
+        // closing the last window exits the app, like bevy's close_when_requested
+        GameState::Menu | GameState::GameOver | GameState::Versus => {
+            for window in closed_windows {
+                commands.entity(window).despawn();
+            }
+        }
+    }
+}
+
+fn confirm_quit(
+    input: Res<Input<KeyCode>>,
+    prompt: Res<QuitPrompt>,
+    mut resume: ResMut<Resume>,
+    mut run_state: ResMut<NextState<GameState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if input.just_pressed(KeyCode::Y) {
+        exit.send(AppExit);
+    } else if input.any_just_pressed([KeyCode::N, KeyCode::Escape]) {
+        resume.0 = prompt.back_to == GameState::Playing;
+        run_state.set(prompt.back_to.clone());
+    }
+}
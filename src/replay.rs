@@ -0,0 +1,352 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    mode::{self, GameMode},
+    power_ups::{PowerUp, PowerUpEvent},
+    storage, BoardConfig, BoardShift, Game, GameRng, GameState, Resume, ShiftEvent, SpawnBias,
+    StartConfig,
+};
+
+pub const REPLAY_FILE: &str = "replay.json";
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ReplayRecorder>()
+            .init_resource::<ReplayPlayer>()
+            .add_system(
+                save_replay
+                    .run_if(not_replaying)
+                    .run_if(mode::not_practice)
+                    .in_schedule(OnEnter(GameState::GameOver)),
+            )
+            .add_system(
+                export_replay
+                    .run_if(mode::not_practice)
+                    .in_set(OnUpdate(GameState::Playing)),
+            )
+            .add_system(load_replay)
+            .add_system(play_replay.run_if(is_replaying));
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayMove {
+    pub index: u32,
+    pub direction: BoardShift,
+}
+
+/// A power-up used after `before_move` moves.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ReplayPowerUp {
+    pub before_move: u32,
+    pub power_up: PowerUp,
+}
+
+/// Everything needed to play a game back: with the same seed and settings
+/// the spawn sequence repeats, so only the moves have to be stored.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub board_width: u8,
+    pub board_height: u8,
+    pub four_spawn_chance: f32,
+    /// Replays recorded before the chance of a 4 scaled with the score keep
+    /// it fixed.
+    #[serde(default)]
+    pub scale_four_chance: bool,
+    #[serde(default = "default_starting_tiles")]
+    pub starting_tiles: usize,
+    /// Replays recorded before the bias existed spawned uniformly.
+    #[serde(default)]
+    pub spawn_bias: SpawnBias,
+    /// Replays recorded before the mode was stored play back as `Classic`.
+    #[serde(default)]
+    pub mode: GameMode,
+    pub score: u32,
+    pub moves: Vec<ReplayMove>,
+    /// Replays recorded before power-ups existed used none.
+    #[serde(default)]
+    pub power_ups: Vec<ReplayPowerUp>,
+}
+
+/// Replays recorded before the count was configurable started with two.
+fn default_starting_tiles() -> usize {
+    2
+}
+
+/// Moves of the current game that changed the board, and the power-ups
+/// used in between.
+#[derive(Default, Resource)]
+pub struct ReplayRecorder {
+    moves: Vec<ReplayMove>,
+    power_ups: Vec<ReplayPowerUp>,
+}
+
+impl ReplayRecorder {
+    pub fn record(&mut self, direction: BoardShift) {
+        let index = self.moves.len() as u32;
+        self.moves.push(ReplayMove { index, direction });
+    }
+
+    pub fn record_power_up(&mut self, power_up: PowerUp) {
+        let before_move = self.moves.len() as u32;
+        self.power_ups.push(ReplayPowerUp {
+            before_move,
+            power_up,
+        });
+    }
+
+    /// Drops the last move, keeping the recording in line with an undo.
+    /// Power-ups used since are dropped too, the undo took them back.
+    pub fn undo(&mut self) {
+        self.moves.pop();
+        let moves = self.moves.len() as u32;
+        self.power_ups
+            .retain(|power_up| power_up.before_move <= moves);
+    }
+
+    pub fn clear(&mut self) {
+        self.moves.clear();
+        self.power_ups.clear();
+    }
+
+    pub fn moves(&self) -> &[ReplayMove] {
+        &self.moves
+    }
+
+    pub fn power_ups(&self) -> &[ReplayPowerUp] {
+        &self.power_ups
+    }
+
+    /// Picks up the recording of a resumed game.
+    pub fn restore(&mut self, moves: Vec<ReplayMove>, power_ups: Vec<ReplayPowerUp>) {
+        self.moves = moves;
+        self.power_ups = power_ups;
+    }
+}
+
+/// Time between two replayed moves.
+const REPLAY_INTERVAL: f32 = 0.25;
+
+/// A replay being watched. It is loaded first and starts once `game_reset`
+/// seeds the new game from it.
+#[derive(Default, Resource)]
+pub struct ReplayPlayer {
+    replay: Option<Replay>,
+    started: bool,
+    next: usize,
+    next_power_up: usize,
+    timer: Timer,
+    /// Mode picked before the replay switched to its own.
+    previous_mode: GameMode,
+}
+
+impl ReplayPlayer {
+    /// Called on every game reset. Starts a loaded replay in its own mode,
+    /// or stops a running one since the player started a new game in the
+    /// middle of it.
+    pub fn begin(&mut self, mode: &mut GameMode) -> Option<&Replay> {
+        if self.started {
+            self.stop(mode);
+        } else if let Some(replay) = &self.replay {
+            self.started = true;
+            self.previous_mode = *mode;
+            *mode = replay.mode;
+        }
+
+        self.replay.as_ref()
+    }
+
+    /// Ends a started replay and puts back the mode picked before it, unless
+    /// another one was picked meanwhile.
+    fn stop(&mut self, mode: &mut GameMode) {
+        if let Some(replay) = self.replay.as_ref().filter(|_| self.started) {
+            if *mode == replay.mode {
+                *mode = self.previous_mode;
+            }
+        }
+        *self = Self::default();
+    }
+}
+
+pub fn is_replaying(player: Res<ReplayPlayer>) -> bool {
+    player.replay.is_some()
+}
+
+pub fn not_replaying(player: Res<ReplayPlayer>) -> bool {
+    player.replay.is_none()
+}
+
+fn load_replay(
+    input: Res<Input<KeyCode>>,
+    board_config: Res<BoardConfig>,
+    start_config: Res<StartConfig>,
+    spawn_bias: Res<SpawnBias>,
+    mut player: ResMut<ReplayPlayer>,
+    mut mode: ResMut<GameMode>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    if !input.just_pressed(KeyCode::F6) {
+        return;
+    }
+
+    let Some(replay) = storage::read::<Replay>(REPLAY_FILE) else {
+        warn!("no replay to play");
+        return;
+    };
+
+    if (replay.board_width, replay.board_height) != (board_config.width, board_config.height) {
+        warn!(
+            "replay is for a {}x{} board, playing on {}x{}",
+            replay.board_width, replay.board_height, board_config.width, board_config.height
+        );
+        return;
+    }
+    if replay.starting_tiles != start_config.starting_tiles {
+        warn!(
+            "replay starts with {} tiles, playing with {}",
+            replay.starting_tiles, start_config.starting_tiles
+        );
+        return;
+    }
+    if replay.spawn_bias != *spawn_bias {
+        warn!(
+            "replay spawns with {:?} bias, playing with {:?}",
+            replay.spawn_bias, *spawn_bias
+        );
+        return;
+    }
+
+    // a replay loaded over a running one starts from the mode picked before
+    player.stop(&mut mode);
+    *player = ReplayPlayer {
+        replay: Some(replay),
+        started: false,
+        next: 0,
+        next_power_up: 0,
+        timer: Timer::from_seconds(REPLAY_INTERVAL, TimerMode::Repeating),
+        previous_mode: GameMode::default(),
+    };
+    run_state.set(GameState::Playing);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn play_replay(
+    time: Res<Time>,
+    state: Res<State<GameState>>,
+    mut player: ResMut<ReplayPlayer>,
+    mut game: ResMut<Game>,
+    mut mode: ResMut<GameMode>,
+    mut resume: ResMut<Resume>,
+    mut run_state: ResMut<NextState<GameState>>,
+    mut shift_events: EventWriter<ShiftEvent>,
+    mut power_up_events: EventWriter<PowerUpEvent>,
+) {
+    if !player.started {
+        return;
+    }
+
+    match state.0 {
+        GameState::Playing => {}
+        // the recording went on past the win, so keep going too
+        GameState::Won => {
+            game.keep_playing = true;
+            resume.0 = true;
+            run_state.set(GameState::Playing);
+            return;
+        }
+        GameState::Menu
+        | GameState::Paused
+        | GameState::ConfirmQuit
+        | GameState::Settings
+        | GameState::Versus => return,
+        GameState::GameOver => {}
+    }
+
+    if !player.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let replay = player.replay.as_ref().expect("expected a replay to play");
+
+    // power-ups go first, each taking a step of their own
+    if let Some(step) = replay
+        .power_ups
+        .get(player.next_power_up)
+        .filter(|step| step.before_move as usize == player.next)
+    {
+        power_up_events.send(PowerUpEvent(step.power_up));
+        player.next_power_up += 1;
+        return;
+    }
+
+    if let Some(step) = replay.moves.get(player.next) {
+        shift_events.send(ShiftEvent(step.direction));
+        player.next += 1;
+        return;
+    }
+
+    if replay.score == game.score {
+        info!("replay finished with the recorded score of {}", game.score);
+    } else {
+        warn!(
+            "replay finished with a score of {}, {} was recorded",
+            game.score, replay.score
+        );
+    }
+    player.stop(&mut mode);
+}
+
+fn save_replay(
+    recorder: Res<ReplayRecorder>,
+    rng: Res<GameRng>,
+    game: Res<Game>,
+    board_config: Res<BoardConfig>,
+    start_config: Res<StartConfig>,
+    spawn_bias: Res<SpawnBias>,
+    mode: Res<GameMode>,
+) {
+    let replay = Replay {
+        seed: rng.seed,
+        board_width: board_config.width,
+        board_height: board_config.height,
+        four_spawn_chance: game.four_spawn_chance,
+        scale_four_chance: game.scale_four_chance,
+        starting_tiles: start_config.starting_tiles,
+        spawn_bias: *spawn_bias,
+        mode: *mode,
+        score: game.score,
+        moves: recorder.moves.clone(),
+        power_ups: recorder.power_ups.clone(),
+    };
+
+    storage::save(REPLAY_FILE, &replay);
+    info!("saved replay of {} moves", replay.moves.len());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn export_replay(
+    input: Res<Input<KeyCode>>,
+    recorder: Res<ReplayRecorder>,
+    rng: Res<GameRng>,
+    game: Res<Game>,
+    board_config: Res<BoardConfig>,
+    start_config: Res<StartConfig>,
+    spawn_bias: Res<SpawnBias>,
+    mode: Res<GameMode>,
+) {
+    if input.just_pressed(KeyCode::F5) {
+        save_replay(
+            recorder,
+            rng,
+            game,
+            board_config,
+            start_config,
+            spawn_bias,
+            mode,
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use std::time::Duration;
+
+use bevy::{app::AppExit, ecs::system::SystemParam, prelude::*};
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colors::TileColors, mode::GameMode, power_ups, replay, spawn_tile, storage, Board, BoardId,
+    FontSpec, Game, GameRng, GameState, GameTimer, NewTileEvent, Points, Position, SeedConfig,
+    UndoBudget,
+};
+
+pub const SAVE_FILE: &str = "savegame.json";
+
+pub struct SaveGamePlugin;
+
+impl Plugin for SaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PendingSave::read())
+            .add_system(
+                start_new_game
+                    .run_if(crate::is_new_game)
+                    .after(crate::game_reset)
+                    .before(crate::clear_resume)
+                    .in_schedule(OnEnter(GameState::Playing)),
+            )
+            .add_system(
+                restore_game
+                    .run_if(replay::not_replaying)
+                    .in_set(OnUpdate(GameState::Playing)),
+            )
+            .add_system(
+                save_on_exit
+                    .run_if(replay::not_replaying)
+                    .in_base_set(CoreSet::Last),
+            )
+            .add_system(
+                autosave
+                    .run_if(replay::not_replaying)
+                    .run_if(in_state(GameState::Playing))
+                    .in_base_set(CoreSet::PostUpdate),
+            )
+            .add_system(clear_abandoned_save.in_schedule(OnEnter(GameState::GameOver)))
+            .add_system(clear_abandoned_save.in_schedule(OnEnter(GameState::Menu)));
+    }
+}
+
+/// Moves between autosaves, 0 saving only when the app closes.
+#[derive(Default, Resource)]
+pub struct AutosaveConfig {
+    pub every: u32,
+}
+
+/// An unfinished game, written when the app closes.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    tiles: Vec<(Position, u32)>,
+    score: u32,
+    move_count: u32,
+    max_tile: u32,
+    #[serde(default)]
+    combo: u32,
+    keep_playing: bool,
+    four_spawn_chance: f32,
+    #[serde(default)]
+    scale_four_chance: bool,
+    seed: u64,
+    rng: ChaCha12Rng,
+    elapsed_secs: f64,
+    moves: Vec<replay::ReplayMove>,
+    /// Undos left, so quitting doesn't refill them. `None` for unlimited
+    /// and older saves, which keep the fresh budget.
+    #[serde(default)]
+    undos_left: Option<u32>,
+    #[serde(default)]
+    power_ups: Vec<replay::ReplayPowerUp>,
+    /// Saves from before the power-ups have every use left.
+    #[serde(default = "default_shuffles_left")]
+    shuffles_left: u32,
+    #[serde(default = "default_removals_left")]
+    removals_left: u32,
+    /// Saves from before the mode was stored are of `Classic` games.
+    #[serde(default)]
+    mode: GameMode,
+}
+
+fn default_shuffles_left() -> u32 {
+    power_ups::SHUFFLES_PER_GAME
+}
+
+fn default_removals_left() -> u32 {
+    power_ups::REMOVALS_PER_GAME
+}
+
+/// Save read at startup, applied once the first game it fits has spawned.
+/// Until then `SAVE_FILE` still holds it, unless another game is saved over
+/// it.
+#[derive(Resource)]
+struct PendingSave {
+    save: Option<SavedGame>,
+    /// Whether a new game just started, for `restore_game` to check the save
+    /// against.
+    due: bool,
+}
+
+impl PendingSave {
+    fn read() -> Self {
+        Self {
+            save: storage::read(SAVE_FILE),
+            due: false,
+        }
+    }
+}
+
+/// Everything a save is made of.
+#[derive(SystemParam)]
+struct CurrentGame<'w, 's> {
+    tiles: Query<'w, 's, (&'static Position, &'static Points)>,
+    game: Res<'w, Game>,
+    rng: Res<'w, GameRng>,
+    timer: Res<'w, GameTimer>,
+    undo_budget: Res<'w, UndoBudget>,
+    recorder: Res<'w, replay::ReplayRecorder>,
+    mode: Res<'w, GameMode>,
+}
+
+impl CurrentGame<'_, '_> {
+    fn to_save(&self) -> SavedGame {
+        SavedGame {
+            tiles: self
+                .tiles
+                .iter()
+                .map(|(pos, points)| (*pos, points.value))
+                .collect(),
+            score: self.game.score,
+            move_count: self.game.move_count,
+            max_tile: self.game.max_tile,
+            combo: self.game.combo,
+            keep_playing: self.game.keep_playing,
+            four_spawn_chance: self.game.four_spawn_chance,
+            scale_four_chance: self.game.scale_four_chance,
+            seed: self.rng.seed,
+            rng: self.rng.rng.clone(),
+            elapsed_secs: self.timer.elapsed.as_secs_f64(),
+            moves: self.recorder.moves().to_vec(),
+            undos_left: self.undo_budget.remaining,
+            power_ups: self.recorder.power_ups().to_vec(),
+            shuffles_left: self.game.shuffles_left,
+            removals_left: self.game.removals_left,
+            mode: *self.mode,
+        }
+    }
+}
+
+fn save_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    state: Res<State<GameState>>,
+    current: CurrentGame,
+) {
+    if exit_events.iter().count() == 0
+        || matches!(
+            state.0,
+            GameState::Menu | GameState::GameOver | GameState::Versus
+        )
+    {
+        return;
+    }
+
+    storage::save(SAVE_FILE, &current.to_save());
+}
+
+/// Saves every `AutosaveConfig::every` moves, on top of the save on exit.
+/// Waits a frame after the move, so the tile it spawns is in the save too.
+fn autosave(
+    config: Res<AutosaveConfig>,
+    current: CurrentGame,
+    mut pending: ResMut<PendingSave>,
+    mut new_tile_events: EventReader<NewTileEvent>,
+    mut due: Local<bool>,
+) {
+    if config.every == 0 {
+        return;
+    }
+    if new_tile_events.iter().count() > 0 {
+        *due = current.game.move_count.is_multiple_of(config.every);
+        return;
+    }
+
+    if std::mem::take(&mut *due) {
+        storage::save(SAVE_FILE, &current.to_save());
+        // the file holds this game now
+        pending.save = None;
+    }
+}
+
+impl SavedGame {
+    /// Why the save can't resume the game just started, if it can't.
+    /// `fixed_seed` is the seed that game must have, if any.
+    fn mismatch(&self, board: &Board, mode: GameMode, fixed_seed: Option<u64>) -> Option<String> {
+        if self
+            .tiles
+            .iter()
+            .any(|(pos, _)| pos.x >= board.width || pos.y >= board.height)
+        {
+            return Some(format!(
+                "it doesn't fit a {}x{} board",
+                board.width, board.height
+            ));
+        }
+        if self.mode != mode {
+            return Some(format!(
+                "it is a {} game, not {}",
+                self.mode.label(),
+                mode.label()
+            ));
+        }
+        if let Some(seed) = fixed_seed.filter(|seed| *seed != self.seed) {
+            return Some(format!(
+                "it has seed {}, the game must have {seed}",
+                self.seed
+            ));
+        }
+        None
+    }
+}
+
+/// A new game abandons the last one, so its save is dropped, but a save
+/// still waiting for a game it fits is kept and checked against this one.
+fn start_new_game(mut pending: ResMut<PendingSave>, player: Res<replay::ReplayPlayer>) {
+    if pending.save.is_none() {
+        clear_save();
+    } else {
+        // a replay is never resumed, nor does it touch the save
+        pending.due = replay::not_replaying(player);
+    }
+}
+
+/// Replaces the freshly spawned starting tiles with the saved board, if the
+/// save fits the game just started.
+#[allow(clippy::too_many_arguments)]
+fn restore_game(
+    mut commands: Commands,
+    mut pending: ResMut<PendingSave>,
+    mode: Res<GameMode>,
+    seed_config: Res<SeedConfig>,
+    query_board: Query<(Entity, &Board)>,
+    tiles: Query<(Entity, &BoardId), With<Position>>,
+    font_spec: Res<FontSpec>,
+    tile_colors: TileColors,
+    mut game: ResMut<Game>,
+    mut rng: ResMut<GameRng>,
+    mut timer: ResMut<GameTimer>,
+    mut recorder: ResMut<replay::ReplayRecorder>,
+    mut undo_budget: ResMut<UndoBudget>,
+) {
+    if !std::mem::take(&mut pending.due) {
+        return;
+    }
+    // saves are only made of games on the one regular board
+    let Some((board_entity, board)) = query_board
+        .iter()
+        .find(|(_, board)| board.id == BoardId::default())
+    else {
+        return;
+    };
+    let Some(save) = pending.save.as_ref() else {
+        return;
+    };
+
+    // daily games are seeded from the date, a save of another day's is kept
+    let fixed_seed = seed_config
+        .seed
+        .or((*mode == GameMode::Daily).then_some(rng.seed));
+    if let Some(reason) = save.mismatch(board, *mode, fixed_seed) {
+        info!("keeping {SAVE_FILE} for another game: {reason}");
+        return;
+    }
+    let Some(save) = pending.save.take() else {
+        return;
+    };
+
+    for (entity, _) in tiles.iter().filter(|(_, id)| **id == board.id) {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (pos, value) in save.tiles {
+        spawn_tile(
+            &mut commands,
+            board_entity,
+            board,
+            &font_spec,
+            &tile_colors,
+            pos,
+            value,
+        );
+    }
+
+    game.score = save.score;
+    game.best_score = game.best_score.max(save.score);
+    game.move_count = save.move_count;
+    game.max_tile = save.max_tile;
+    game.combo = save.combo;
+    game.keep_playing = save.keep_playing;
+    game.four_spawn_chance = save.four_spawn_chance;
+    game.scale_four_chance = save.scale_four_chance;
+    game.shuffles_left = save.shuffles_left;
+    game.removals_left = save.removals_left;
+    rng.seed = save.seed;
+    rng.rng = save.rng;
+    timer.elapsed = Duration::from_secs_f64(save.elapsed_secs);
+    recorder.restore(save.moves, save.power_ups);
+    if let (Some(remaining), Some(left)) = (&mut undo_budget.remaining, save.undos_left) {
+        *remaining = (*remaining).min(left);
+    }
+    info!("resumed game with seed {}", rng.seed);
+}
+
+fn clear_save() {
+    storage::remove(SAVE_FILE);
+}
+
+/// Going back to the menu abandons the game too, and a game over ends it,
+/// but neither drops a save still waiting for a game it fits.
+fn clear_abandoned_save(pending: Res<PendingSave>) {
+    if pending.save.is_none() {
+        clear_save();
+    }
+}
@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{cli::Args, storage, AnimationConfig, BoardConfig, GameState, Resume, BOARD_SIZES};
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// The settings overlay, opened with O during a game. The game waits in
+/// `GameState::Settings` meanwhile and carries on when it closes.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsPrompt>()
+            .add_event::<CloseSettings>()
+            .add_startup_system(load_settings.before(crate::spawn_board))
+            .add_system(toggle_settings);
+    }
+}
+
+/// Settings without a file of their own, the theme, colorblind mode and
+/// audio settings keep theirs.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+struct SavedSettings {
+    slide_ms: u64,
+    board_size: (u8, u8),
+}
+
+impl Default for SavedSettings {
+    fn default() -> Self {
+        let board = BoardConfig::default();
+
+        Self {
+            slide_ms: AnimationConfig::default().slide_ms,
+            board_size: (board.width, board.height),
+        }
+    }
+}
+
+/// State to go back to when the settings close.
+#[derive(Default, Resource)]
+struct SettingsPrompt {
+    back_to: GameState,
+}
+
+/// Sent by the Close button of the overlay.
+pub struct CloseSettings;
+
+fn load_settings(
+    args: Res<Args>,
+    mut animation: ResMut<AnimationConfig>,
+    mut board: ResMut<BoardConfig>,
+) {
+    let Some(saved) = storage::read::<SavedSettings>(SETTINGS_FILE) else {
+        return;
+    };
+
+    animation.slide_ms = saved.slide_ms;
+    // --size beats the saved size
+    if args.size.is_none() && BOARD_SIZES.contains(&saved.board_size) {
+        (board.width, board.height) = saved.board_size;
+    }
+}
+
+/// O opens the settings from a game, paused or not. O, Esc or the Close
+/// button go back to where they were opened from.
+fn toggle_settings(
+    input: Res<Input<KeyCode>>,
+    mut close_events: EventReader<CloseSettings>,
+    state: Res<State<GameState>>,
+    mut prompt: ResMut<SettingsPrompt>,
+    mut resume: ResMut<Resume>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    let closed = close_events.iter().count() > 0;
+
+    match state.0 {
+        GameState::Playing | GameState::Paused if input.just_pressed(KeyCode::O) => {
+            prompt.back_to = state.0.clone();
+            run_state.set(GameState::Settings);
+        }
+        GameState::Settings if closed || input.any_just_pressed([KeyCode::O, KeyCode::Escape]) => {
+            resume.0 = prompt.back_to == GameState::Playing;
+            run_state.set(prompt.back_to.clone());
+        }
+        _ => {}
+    }
+}
+
+/// Called by the settings overlay, so picking a board size in the menu or
+/// loading a replay doesn't stick.
+pub fn save(animation: &AnimationConfig, board: &BoardConfig) {
+    storage::save(
+        SETTINGS_FILE,
+        &SavedSettings {
+            slide_ms: animation.slide_ms,
+            board_size: (board.width, board.height),
+        },
+    );
+}
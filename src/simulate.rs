@@ -0,0 +1,153 @@
+use rand::prelude::*;
+use rand_chacha::ChaCha12Rng;
+
+use crate::{shift_grid, BoardShift, Grid};
+
+/// Score from which each chance of a 4 applies, lowest first.
+const FOUR_CHANCE_STEPS: [(u32, f32); 4] = [(0, 0.1), (5_000, 0.15), (20_000, 0.2), (50_000, 0.25)];
+
+/// Chance of a new tile being a 4, growing with the score so the late game
+/// gets harder.
+pub fn four_chance_for_score(score: u32) -> f32 {
+    FOUR_CHANCE_STEPS
+        .iter()
+        .rev()
+        .find(|(threshold, _)| score >= *threshold)
+        .map_or(FOUR_CHANCE_STEPS[0].1, |(_, chance)| *chance)
+}
+
+/// Puts a 2, or a 4 with `four_chance`, on a random free cell. A full grid
+/// is left alone.
+pub fn spawn_tile(grid: &mut Grid, rng: &mut impl Rng, four_chance: f32) {
+    if let Some(pos) = grid.empty_positions().choose(rng) {
+        let value = if rng.gen_bool(f64::from(four_chance.clamp(0.0, 1.0))) {
+            4
+        } else {
+            2
+        };
+        grid.set(pos.x, pos.y, Some(value));
+    }
+}
+
+/// Board and spawn rules of a simulated game.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationConfig {
+    pub width: u8,
+    pub height: u8,
+    pub starting_tiles: usize,
+    /// Fixed chance of a 4, or `None` to scale it with the score like the
+    /// game does.
+    pub four_chance: Option<f32>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            width: 4,
+            height: 4,
+            starting_tiles: 2,
+            four_chance: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameResult {
+    pub score: u32,
+    pub max_tile: u32,
+    pub moves: u32,
+}
+
+/// Plays a game until `policy` returns `None`, or picks a move that
+/// changes nothing, and returns how it went. The policy gets the game's
+/// rng for random choices, so a seed and a deterministic policy always
+/// play the same game. Scores don't include the combo multiplier.
+pub fn simulate_game(
+    config: &SimulationConfig,
+    seed: u64,
+    mut policy: impl FnMut(&Grid, &mut ChaCha12Rng) -> Option<BoardShift>,
+) -> GameResult {
+    let mut rng = ChaCha12Rng::seed_from_u64(seed);
+    let mut grid = Grid::new(config.width, config.height);
+    let mut score = 0;
+    let mut moves = 0;
+
+    let four_chance = |score| {
+        config
+            .four_chance
+            .unwrap_or_else(|| four_chance_for_score(score))
+    };
+
+    for _ in 0..config.starting_tiles {
+        spawn_tile(&mut grid, &mut rng, four_chance(score));
+    }
+
+    while let Some(dir) = policy(&grid, &mut rng) {
+        let (next, gain, moved) = shift_grid(&grid, dir);
+        if !moved {
+            break;
+        }
+        grid = next;
+        score += gain;
+        moves += 1;
+        spawn_tile(&mut grid, &mut rng, four_chance(score));
+    }
+
+    GameResult {
+        score,
+        max_tile: grid.max_value(),
+        moves,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRECTIONS: [BoardShift; 4] = [
+        BoardShift::Down,
+        BoardShift::Left,
+        BoardShift::Right,
+        BoardShift::Up,
+    ];
+
+    /// Plays a random move that changes the board, until none does.
+    fn random_policy(grid: &Grid, rng: &mut ChaCha12Rng) -> Option<BoardShift> {
+        let moves = DIRECTIONS
+            .into_iter()
+            .filter(|dir| shift_grid(grid, *dir).2)
+            .collect::<Vec<_>>();
+        moves.choose(rng).copied()
+    }
+
+    #[test]
+    fn same_seed_plays_the_same_game() {
+        let config = SimulationConfig::default();
+
+        let first = simulate_game(&config, 7, random_policy);
+        let second = simulate_game(&config, 7, random_policy);
+
+        assert_eq!(first, second);
+        assert!(first.moves > 0);
+    }
+
+    #[test]
+    fn different_seeds_play_different_games() {
+        let config = SimulationConfig::default();
+
+        assert_ne!(
+            simulate_game(&config, 1, random_policy),
+            simulate_game(&config, 2, random_policy)
+        );
+    }
+
+    #[test]
+    fn no_move_from_the_policy_ends_the_game_at_once() {
+        let config = SimulationConfig::default();
+        let result = simulate_game(&config, 3, |_, _| None);
+
+        assert_eq!(result.moves, 0);
+        assert_eq!(result.score, 0);
+        assert!(matches!(result.max_tile, 2 | 4));
+    }
+}
@@ -0,0 +1,39 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+/// Despawns every entity tagged with `StateScoped<S>` when leaving its state.
+pub struct StateScopedPlugin<S: States>(PhantomData<S>);
+
+impl<S: States> Default for StateScopedPlugin<S> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<S: States> Plugin for StateScopedPlugin<S> {
+    fn build(&self, app: &mut App) {
+        // `State<S>` already holds the next state during `OnExit`, so every
+        // state gets its own system knowing which one is being left
+        for state in S::variants() {
+            app.add_system(despawn_state_scoped(state.clone()).in_schedule(OnExit(state)));
+        }
+    }
+}
+
+/// Ties an entity to a state, it is despawned with its children once the
+/// state is left, re-entering the same state included.
+#[derive(Component)]
+pub struct StateScoped<S: States>(pub S);
+
+fn despawn_state_scoped<S: States>(
+    state: S,
+) -> impl FnMut(Commands, Query<(Entity, &StateScoped<S>)>) {
+    move |mut commands, scoped| {
+        for (entity, scoped) in scoped.iter() {
+            if scoped.0 == state {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+use bevy::{app::AppExit, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colors, daily::DailyChallenge, mode, replay, storage, FontSpec, GameOverEvent, GameState,
+    MergeEvent,
+};
+
+pub const STATS_FILE: &str = "stats.json";
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(storage::load::<Stats>(STATS_FILE))
+            .add_system(
+                count_merges
+                    .run_if(replay::not_replaying)
+                    .run_if(mode::not_practice)
+                    // versus merges aren't part of anyone's stats
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_system(
+                record_game
+                    .run_if(replay::not_replaying)
+                    .run_if(mode::not_practice),
+            )
+            // games left unfinished have their merges counted too
+            .add_system(save_on_exit.in_base_set(CoreSet::Last))
+            .add_system(toggle_stats_panel);
+    }
+}
+
+/// Lifetime totals over every game played.
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Stats {
+    pub games_played: u32,
+    pub total_merges: u32,
+    pub highest_tile: u32,
+    pub best_score: u32,
+    pub play_time_secs: f64,
+    /// Best score of each daily challenge, keyed by its date.
+    pub daily_best: BTreeMap<String, u32>,
+}
+
+#[derive(Component)]
+struct StatsPanel;
+
+/// Merges undone later still count, they were made all the same.
+fn count_merges(mut merge_events: EventReader<MergeEvent>, mut stats: ResMut<Stats>) {
+    for event in merge_events.iter() {
+        stats.total_merges += 1;
+        stats.highest_tile = stats.highest_tile.max(event.points);
+    }
+}
+
+fn record_game(
+    mut game_over_events: EventReader<GameOverEvent>,
+    daily: Res<DailyChallenge>,
+    mut stats: ResMut<Stats>,
+) {
+    let Some(game) = game_over_events.iter().last() else {
+        return;
+    };
+
+    stats.games_played += 1;
+    stats.best_score = stats.best_score.max(game.score);
+    stats.highest_tile = stats.highest_tile.max(game.max_tile);
+    stats.play_time_secs += game.duration.as_secs_f64();
+    if let Some(date) = daily.date {
+        let best = stats.daily_best.entry(date.to_string()).or_default();
+        *best = (*best).max(game.score);
+    }
+
+    storage::save(STATS_FILE, &*stats);
+}
+
+fn save_on_exit(mut exit_events: EventReader<AppExit>, stats: Res<Stats>) {
+    if exit_events.iter().count() > 0 {
+        storage::save(STATS_FILE, &*stats);
+    }
+}
+
+fn toggle_stats_panel(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    stats: Res<Stats>,
+    font_spec: Res<FontSpec>,
+    panels: Query<Entity, With<StatsPanel>>,
+) {
+    if !input.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    if let Ok(panel) = panels.get_single() {
+        commands.entity(panel).despawn_recursive();
+        return;
+    }
+
+    let minutes = (stats.play_time_secs / 60.0) as u64;
+    let mut lines = vec![
+        format!("Games played: {}", stats.games_played),
+        format!("Total merges: {}", stats.total_merges),
+        format!("Highest tile: {}", stats.highest_tile),
+        format!("Best score: {}", stats.best_score),
+        format!("Play time: {}h {:02}m", minutes / 60, minutes % 60),
+    ];
+    // dates sort as strings, so the last entry is the latest daily
+    if let Some((date, score)) = stats.daily_best.iter().next_back() {
+        lines.push(format!("Daily {date}: {score}"));
+    }
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(50.0),
+                        bottom: Val::Px(50.0),
+                        ..default()
+                    },
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(20.0)),
+                    gap: Size::all(Val::Px(8.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(colors::SCORE_BOX),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            StatsPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Statistics",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            ));
+            for line in lines {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: font_spec.family.clone(),
+                        font_size: 18.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+        });
+}
@@ -0,0 +1,61 @@
+use std::{fs, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+pub const SCORES_FILE: &str = "scores.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Scores {
+    pub best_score: u32,
+}
+
+/// Save files live next to the executable.
+fn path(file_name: &str) -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.to_path_buf()))
+        .unwrap_or_default()
+        .join(file_name)
+}
+
+/// Reads a save file, returning `None` when it is missing or corrupt.
+pub fn read<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let contents = fs::read_to_string(path(file_name)).ok()?;
+
+    serde_json::from_str(&contents)
+        .map_err(|err| warn!("ignoring corrupt {file_name}: {err}"))
+        .ok()
+}
+
+/// Reads a save file, falling back to the default when it is missing or corrupt.
+pub fn load<T: DeserializeOwned + Default>(file_name: &str) -> T {
+    read(file_name).unwrap_or_default()
+}
+
+/// Writes to a temporary file first and renames it over the old one, so a
+/// crash halfway through leaves the previous save intact.
+pub fn save<T: Serialize>(file_name: &str, value: &T) {
+    let path = path(file_name);
+    let temp_path = path.with_extension("json.tmp");
+
+    let result = serde_json::to_string_pretty(value)
+        .map_err(|err| err.to_string())
+        .and_then(|json| fs::write(&temp_path, json).map_err(|err| err.to_string()))
+        .and_then(|()| fs::rename(&temp_path, &path).map_err(|err| err.to_string()));
+
+    if let Err(err) = result {
+        warn!("could not write {file_name}: {err}");
+    }
+}
+
+/// Deletes a save file, if there is one.
+pub fn remove(file_name: &str) {
+    let path = path(file_name);
+
+    if path.exists() {
+        if let Err(err) = fs::remove_file(path) {
+            warn!("could not remove {file_name}: {err}");
+        }
+    }
+}
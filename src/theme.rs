@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::{
+    colors::{ColorblindMode, Theme, TileColors},
+    storage, Board, Points, TilePlaceholder, TileShadow,
+};
+
+pub const THEME_FILE: &str = "theme.json";
+pub const COLORBLIND_FILE: &str = "colorblind.json";
+
+pub struct ThemePlugin;
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        let theme = storage::load::<Theme>(THEME_FILE);
+
+        app.insert_resource(theme)
+            .insert_resource(storage::load::<ColorblindMode>(COLORBLIND_FILE))
+            .insert_resource(ClearColor(theme.palette().clear))
+            .add_system(cycle_theme)
+            .add_system(toggle_colorblind_mode)
+            .add_system(apply_theme.after(cycle_theme).after(toggle_colorblind_mode))
+            .add_system(save_theme.after(cycle_theme).after(toggle_colorblind_mode));
+    }
+}
+
+fn cycle_theme(input: Res<Input<KeyCode>>, mut theme: ResMut<Theme>) {
+    if input.just_pressed(KeyCode::T) {
+        *theme = theme.next();
+        info!("theme: {:?}", *theme);
+    }
+}
+
+fn toggle_colorblind_mode(input: Res<Input<KeyCode>>, mut colorblind: ResMut<ColorblindMode>) {
+    if input.just_pressed(KeyCode::C) {
+        colorblind.0 = !colorblind.0;
+        info!("colorblind mode: {}", colorblind.0);
+    }
+}
+
+/// Saves the theme and the colorblind mode whenever they change, from the
+/// keys or the settings.
+fn save_theme(theme: Res<Theme>, colorblind: Res<ColorblindMode>) {
+    if theme.is_changed() && !theme.is_added() {
+        storage::save(THEME_FILE, &*theme);
+    }
+    if colorblind.is_changed() && !colorblind.is_added() {
+        storage::save(COLORBLIND_FILE, &*colorblind);
+    }
+}
+
+/// Recolors everything already on screen when the theme or the colorblind
+/// mode changes.
+#[allow(clippy::type_complexity)]
+fn apply_theme(
+    theme: Res<Theme>,
+    tile_colors: TileColors,
+    mut clear_color: ResMut<ClearColor>,
+    mut boards: Query<&mut Sprite, (With<Board>, Without<TilePlaceholder>, Without<Points>)>,
+    mut placeholders: Query<&mut Sprite, (With<TilePlaceholder>, Without<Points>)>,
+    mut tiles: Query<(&Points, &Children, &mut Sprite)>,
+    mut shadows: Query<
+        &mut Sprite,
+        (
+            With<TileShadow>,
+            Without<Points>,
+            Without<Board>,
+            Without<TilePlaceholder>,
+        ),
+    >,
+) {
+    if !tile_colors.is_changed() || theme.is_added() {
+        return;
+    }
+
+    let palette = theme.palette();
+    clear_color.0 = palette.clear;
+
+    for mut sprite in boards.iter_mut() {
+        sprite.color = palette.board;
+    }
+    for mut sprite in placeholders.iter_mut() {
+        sprite.color = palette.tile_placeholder;
+    }
+    for (points, children, mut sprite) in tiles.iter_mut() {
+        sprite.color = tile_colors.tile(points.value);
+
+        let mut shadows = shadows.iter_many_mut(children);
+        while let Some(mut shadow) = shadows.fetch_next() {
+            shadow.color = tile_colors.shadow(points.value);
+        }
+    }
+}
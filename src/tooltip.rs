@@ -0,0 +1,76 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    cursor_cell, cursor_world_position, state_scoped::StateScoped, Board, BoardId, FontSpec,
+    GameState, Points, Position,
+};
+
+/// Shows what the tile under the cursor scores when it merges.
+pub struct TooltipPlugin;
+
+impl Plugin for TooltipPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(update_tile_tooltip.in_set(OnUpdate(GameState::Playing)));
+    }
+}
+
+#[derive(Component)]
+struct TileTooltip;
+
+/// Distance from the cursor to the bottom left of the tooltip.
+const TOOLTIP_OFFSET: Vec2 = Vec2::new(12.0, 12.0);
+
+/// Merging two tiles of a value scores their sum, which is what the tooltip
+/// shows. Leaving the tile removes it.
+fn update_tile_tooltip(
+    mut commands: Commands,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    boards: Query<(&Board, &GlobalTransform)>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    mut tooltips: Query<(Entity, &mut Text, &mut Transform), With<TileTooltip>>,
+    font_spec: Res<FontSpec>,
+) {
+    let hovered = boards.iter().find_map(|(board, board_transform)| {
+        let cell = cursor_cell(&windows, &cameras, board, board_transform)?;
+        tiles
+            .iter()
+            .find(|(pos, _, id)| **pos == cell && **id == board.id)
+            .map(|(_, points, _)| points.value)
+    });
+    let cursor = cursor_world_position(&windows, &cameras);
+
+    let (Some(value), Some(cursor)) = (hovered, cursor) else {
+        for (entity, ..) in tooltips.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+
+    let label = format!("worth {} points when merged", value * 2);
+    let translation = (cursor + TOOLTIP_OFFSET).extend(10.0);
+
+    if let Ok((_, mut text, mut transform)) = tooltips.get_single_mut() {
+        text.sections[0].value = label;
+        transform.translation = translation;
+        return;
+    }
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                label,
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 16.0,
+                    color: Color::WHITE,
+                },
+            ),
+            text_anchor: bevy::sprite::Anchor::BottomLeft,
+            transform: Transform::from_translation(translation),
+            ..default()
+        },
+        TileTooltip,
+        StateScoped(GameState::Playing),
+    ));
+}
@@ -1,7 +1,21 @@
+use crate::audio::AudioSettings;
 use crate::colors;
+use crate::daily::DailyChallenge;
+use crate::keybindings::KeyBindings;
+use crate::mode::{GameMode, TIME_ATTACK_LIMIT};
+use crate::settings;
+use crate::state_scoped::StateScoped;
+use crate::AnimationConfig;
+use crate::BoardConfig;
+use crate::BoardShift;
 use crate::FontSpec;
 use crate::Game;
+use crate::GameOverEvent;
 use crate::GameState;
+use crate::GameTimer;
+use crate::MoveHistory;
+use crate::RetrySeed;
+use crate::UndoBudget;
 use bevy::prelude::*;
 
 mod styles;
@@ -10,18 +24,198 @@ pub struct GameUIPlugin;
 
 impl Plugin for GameUIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_ui);
-        app.add_systems((scoreboard, button_interaction_system, button_text_system));
+        app.add_startup_system(setup_ui)
+            .init_resource::<MenuSelection>();
+        app.add_systems((
+            scoreboard,
+            max_tile_display,
+            combo_display,
+            moves_display,
+            undo_display,
+            timer_display,
+            daily_display,
+            button_color_system,
+            button_interaction_system,
+            restart_button_system,
+            retry_button_system,
+            button_text_system,
+            menu_button_system,
+            menu_labels,
+            move_history_display,
+        ))
+        .add_system(shuffle_display)
+        .add_system(removal_display)
+        .add_system(setup_menu.in_schedule(OnEnter(GameState::Menu)))
+        .add_systems(
+            (
+                navigate_menu.before(menu_button_system),
+                highlight_menu_selection.after(button_color_system),
+            )
+                .in_set(OnUpdate(GameState::Menu)),
+        )
+        .add_system(setup_win_overlay.in_schedule(OnEnter(GameState::Won)))
+        .add_system(setup_game_over_overlay.in_schedule(OnEnter(GameState::GameOver)))
+        .add_system(setup_pause_overlay.in_schedule(OnEnter(GameState::Paused)))
+        .add_system(setup_quit_overlay.in_schedule(OnEnter(GameState::ConfirmQuit)))
+        .add_system(setup_settings_overlay.in_schedule(OnEnter(GameState::Settings)))
+        .add_systems(
+            (
+                settings_button_system,
+                settings_labels.after(settings_button_system),
+            )
+                .in_set(OnUpdate(GameState::Settings)),
+        );
     }
 }
 
-#[derive(Component)]
-pub struct ScoreDisplay;
+/// Counts up to the score instead of jumping to it, see `scoreboard`.
+#[derive(Component, Default)]
+pub struct ScoreDisplay {
+    /// Kept fractional, rounding each frame's step up would count faster
+    /// the higher the frame rate.
+    shown: f32,
+}
+
+/// Roughly how long the score takes to count up to a new value.
+const SCORE_COUNT_SECS: f32 = 0.2;
+
+impl ScoreDisplay {
+    /// Counts `delta_secs` further towards `target`.
+    fn step(&mut self, target: u32, delta_secs: f32) {
+        let target = target as f32;
+        if target < self.shown {
+            self.shown = target;
+        }
+        // exponential approach, within a couple of percent after SCORE_COUNT_SECS
+        let share = 1.0 - (-4.0 * delta_secs / SCORE_COUNT_SECS).exp();
+        self.shown += (target - self.shown) * share;
+        if target - self.shown < 0.5 {
+            self.shown = target;
+        }
+    }
+}
 
 #[derive(Component)]
 pub struct BestScoreDisplay;
 
+#[derive(Component)]
+pub struct MaxTileDisplay;
+
+#[derive(Component)]
+pub struct MovesDisplay;
+
+#[derive(Component)]
+pub struct ComboDisplay;
+
+#[derive(Component)]
+pub struct UndoDisplay;
+
+#[derive(Component)]
+pub struct ShuffleDisplay;
+
+#[derive(Component)]
+pub struct RemovalDisplay;
+
+#[derive(Component)]
+pub struct TimerDisplay;
+
+/// Date and seed of the daily challenge, empty in other modes.
+#[derive(Component)]
+pub struct DailyDisplay;
+
+/// The last few moves, newest on top.
+#[derive(Component)]
+pub struct MoveHistoryDisplay;
+
+#[derive(Component)]
+pub struct WinOverlay;
+
+#[derive(Component)]
+pub struct PauseOverlay;
+
+#[derive(Component)]
+pub struct GameOverOverlay;
+
+#[derive(Component)]
+pub struct QuitOverlay;
+
+#[derive(Component)]
+pub struct MenuOverlay;
+
+/// The End Game / New Game button in the header.
+#[derive(Component)]
+pub struct GameButton;
+
+/// Starts a fresh game from any state.
+#[derive(Component)]
+pub struct RestartButton;
+
+/// Plays the game that just ended again, on the same seed.
+#[derive(Component)]
+pub struct RetryButton;
+
+/// "Keep going" button on the win overlay.
+#[derive(Component)]
+pub struct ContinueButton;
+
+/// Starts a game with the size and mode picked in the menu.
+#[derive(Component)]
+pub struct StartButton;
+
+/// Starts a two player game, see `versus`.
+#[derive(Component)]
+pub struct VersusButton;
+
+/// Cycles through `BOARD_SIZES`.
+#[derive(Component)]
+pub struct BoardSizeButton;
+
+/// Cycles through the game modes.
+#[derive(Component)]
+pub struct GameModeButton;
+
+/// Goes back to the menu from the game over overlay.
+#[derive(Component)]
+pub struct MenuButton;
+
+#[derive(Component)]
+pub struct SettingsOverlay;
+
+/// A row of the settings overlay, clicking it steps the setting on.
+#[derive(Component, Clone, Copy)]
+pub enum SettingButton {
+    Theme,
+    Colorblind,
+    Sound,
+    Volume,
+    Animation,
+    BoardSize,
+    Close,
+}
+
+/// Menu button picked with the keyboard, counted among the menu buttons
+/// from the top.
+#[derive(Default, Resource)]
+pub struct MenuSelection {
+    index: usize,
+    /// Button clicked from the keyboard last frame, released on the next one.
+    pressed: Option<Entity>,
+}
+
 fn setup_ui(mut commands: Commands, font_spec: Res<FontSpec>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_spec.family.clone(),
+                font_size: 18.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(styles::MOVE_HISTORY),
+        MoveHistoryDisplay,
+    ));
+
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -34,14 +228,36 @@ fn setup_ui(mut commands: Commands, font_spec: Res<FontSpec>) {
             ..default()
         })
         .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                "2048 - Use arrow keys",
-                TextStyle {
-                    font: font_spec.family.clone(),
-                    font_size: 40.0,
-                    color: Color::WHITE,
-                },
-            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        gap: Size::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "2048 - Use arrow keys or WASD",
+                        TextStyle {
+                            font: font_spec.family.clone(),
+                            font_size: 40.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: font_spec.family.clone(),
+                                font_size: 20.0,
+                                color: Color::WHITE,
+                            },
+                        ),
+                        DailyDisplay,
+                    ));
+                });
 
             parent
                 .spawn(NodeBundle {
@@ -54,132 +270,677 @@ fn setup_ui(mut commands: Commands, font_spec: Res<FontSpec>) {
                     ..default()
                 })
                 .with_children(|parent| {
-                    // scorebox
-                    parent
-                        .spawn(NodeBundle {
-                            style: styles::SCORE_CONTAINER,
-                            background_color: BackgroundColor(colors::SCORE_BOX),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            parent.spawn(
-                                TextBundle::from_section(
-                                    "Score",
-                                    TextStyle {
-                                        font: font_spec.family.clone(),
-                                        font_size: 15.0,
-                                        color: Color::WHITE,
-                                    },
-                                )
-                                .with_text_alignment(TextAlignment::Center),
-                            );
-                            parent.spawn((
-                                TextBundle::from_section(
-                                    "<score>",
-                                    TextStyle {
-                                        font: font_spec.family.clone(),
-                                        font_size: 20.0,
-                                        color: Color::WHITE,
-                                    },
-                                )
-                                .with_text_alignment(TextAlignment::Center),
-                                ScoreDisplay,
-                            ));
-                        });
-                    // end scorebox
-                    // best scorebox
-                    parent
-                        .spawn(NodeBundle {
-                            style: styles::SCORE_CONTAINER,
-                            background_color: BackgroundColor(colors::SCORE_BOX),
-                            ..default()
-                        })
-                        .with_children(|parent| {
-                            parent.spawn(
-                                TextBundle::from_section(
-                                    "Best",
-                                    TextStyle {
-                                        font: font_spec.family.clone(),
-                                        font_size: 15.0,
-                                        color: Color::WHITE,
-                                    },
-                                )
-                                .with_text_alignment(TextAlignment::Center),
-                            );
-                            parent.spawn((
-                                TextBundle::from_section(
-                                    "<score>",
-                                    TextStyle {
-                                        font: font_spec.family.clone(),
-                                        font_size: 20.0,
-                                        color: Color::WHITE,
-                                    },
-                                )
-                                .with_text_alignment(TextAlignment::Center),
-                                BestScoreDisplay,
-                            ));
-                        });
-                    // end best scorebox
+                    spawn_score_box(parent, &font_spec, "Score", ScoreDisplay::default());
+                    spawn_score_box(parent, &font_spec, "Best", BestScoreDisplay);
+                    spawn_score_box(parent, &font_spec, "Max tile", MaxTileDisplay);
+                    spawn_score_box(parent, &font_spec, "Combo", ComboDisplay);
+                    spawn_score_box(parent, &font_spec, "Moves", MovesDisplay);
+                    spawn_score_box(parent, &font_spec, "Undos", UndoDisplay);
+                    spawn_score_box(parent, &font_spec, "Shuffles", ShuffleDisplay);
+                    spawn_score_box(parent, &font_spec, "Removals", RemovalDisplay);
+                    spawn_score_box(parent, &font_spec, "Time", TimerDisplay);
                 });
 
             parent
-                .spawn(ButtonBundle {
+                .spawn(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Px(130.0), Val::Px(50.0)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
+                        flex_direction: FlexDirection::Column,
+                        gap: Size::all(Val::Px(10.0)),
                         ..default()
                     },
                     ..default()
                 })
                 .with_children(|parent| {
-                    parent.spawn(TextBundle {
-                        text: Text::from_section(
-                            "Button",
-                            TextStyle {
-                                font: font_spec.family.clone(),
-                                font_size: 20.0,
-                                color: Color::rgb(0.9, 0.9, 0.9),
-                            },
-                        ),
-                        ..default()
-                    });
+                    spawn_button(parent, &font_spec, "Button", GameButton);
+                    spawn_button(parent, &font_spec, "New Game", RestartButton);
                 });
         });
 }
 
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    font_spec: &FontSpec,
+    label: &str,
+    marker: impl Component,
+) {
+    spawn_styled_button(parent, font_spec, styles::BUTTON, label, marker);
+}
+
+fn spawn_styled_button(
+    parent: &mut ChildBuilder,
+    font_spec: &FontSpec,
+    style: Style,
+    label: &str,
+    marker: impl Component,
+) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style,
+                background_color: colors::button::NORMAL.into(),
+                ..default()
+            },
+            marker,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ));
+        });
+}
+
+fn spawn_score_box(
+    parent: &mut ChildBuilder,
+    font_spec: &FontSpec,
+    label: &str,
+    marker: impl Component,
+) {
+    parent
+        .spawn(NodeBundle {
+            style: styles::SCORE_CONTAINER,
+            background_color: BackgroundColor(colors::SCORE_BOX),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(
+                TextBundle::from_section(
+                    label,
+                    TextStyle {
+                        font: font_spec.family.clone(),
+                        font_size: 15.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+            );
+            parent.spawn((
+                TextBundle::from_section(
+                    "<score>",
+                    TextStyle {
+                        font: font_spec.family.clone(),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_text_alignment(TextAlignment::Center),
+                marker,
+            ));
+        });
+}
+
+fn setup_win_overlay(mut commands: Commands, font_spec: Res<FontSpec>, game: Res<Game>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: styles::OVERLAY,
+                background_color: BackgroundColor(colors::OVERLAY),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            WinOverlay,
+            StateScoped(GameState::Won),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "You win!",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                format!("You reached {}", game.win_target),
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            ));
+            spawn_button(parent, &font_spec, "Keep going", ContinueButton);
+        });
+}
+
+fn setup_game_over_overlay(
+    mut commands: Commands,
+    font_spec: Res<FontSpec>,
+    mut game_over_events: EventReader<GameOverEvent>,
+    mode: Res<GameMode>,
+    timer: Res<GameTimer>,
+    game: Res<Game>,
+) {
+    // every way into GameOver sends the event, the fallback is only there
+    // to never show an empty overlay
+    let summary = game_over_events
+        .iter()
+        .last()
+        .cloned()
+        .unwrap_or_else(|| GameOverEvent::new(&game, &timer));
+    let title = if mode.time_is_up(&timer) {
+        "Time's up!"
+    } else {
+        "Game Over"
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: styles::OVERLAY,
+                background_color: BackgroundColor(colors::OVERLAY),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            GameOverOverlay,
+            StateScoped(GameState::GameOver),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ));
+            for line in [
+                format!("{} mode", mode.label()),
+                format!("Score: {}", summary.score),
+                format!("Best: {}", summary.best_score),
+                format!("Moves: {}", summary.moves),
+            ] {
+                parent.spawn(TextBundle::from_section(
+                    line,
+                    TextStyle {
+                        font: font_spec.family.clone(),
+                        font_size: 25.0,
+                        color: Color::WHITE,
+                    },
+                ));
+            }
+            spawn_button(parent, &font_spec, "Play again", RestartButton);
+            spawn_button(parent, &font_spec, "Retry", RetryButton);
+            spawn_button(parent, &font_spec, "Menu", MenuButton);
+        });
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    font_spec: Res<FontSpec>,
+    mut selection: ResMut<MenuSelection>,
+) {
+    *selection = MenuSelection::default();
+    commands
+        .spawn((
+            NodeBundle {
+                style: styles::OVERLAY,
+                background_color: BackgroundColor(colors::OVERLAY),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            MenuOverlay,
+            StateScoped(GameState::Menu),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "2048",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 80.0,
+                    color: Color::WHITE,
+                },
+            ));
+            spawn_styled_button(
+                parent,
+                &font_spec,
+                styles::MENU_BUTTON,
+                "Start",
+                StartButton,
+            );
+            // labels are filled in by menu_labels
+            spawn_styled_button(parent, &font_spec, styles::MENU_BUTTON, "", BoardSizeButton);
+            spawn_styled_button(parent, &font_spec, styles::MENU_BUTTON, "", GameModeButton);
+            spawn_styled_button(
+                parent,
+                &font_spec,
+                styles::MENU_BUTTON,
+                "Versus",
+                VersusButton,
+            );
+        });
+}
+
+#[allow(clippy::type_complexity)]
+fn clicked<T: Component>(query: &Query<&Interaction, (Changed<Interaction>, With<T>)>) -> bool {
+    query
+        .iter()
+        .any(|interaction| *interaction == Interaction::Clicked)
+}
+
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn menu_button_system(
+    start_query: Query<&Interaction, (Changed<Interaction>, With<StartButton>)>,
+    size_query: Query<&Interaction, (Changed<Interaction>, With<BoardSizeButton>)>,
+    mode_query: Query<&Interaction, (Changed<Interaction>, With<GameModeButton>)>,
+    versus_query: Query<&Interaction, (Changed<Interaction>, With<VersusButton>)>,
+    menu_query: Query<&Interaction, (Changed<Interaction>, With<MenuButton>)>,
+    mut config: ResMut<BoardConfig>,
+    mut mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if clicked(&start_query) {
+        next_state.set(GameState::Playing);
+    }
+    if clicked(&size_query) {
+        config.next_size();
+    }
+    if clicked(&mode_query) {
+        *mode = mode.next();
+    }
+    if clicked(&versus_query) {
+        next_state.set(GameState::Versus);
+    }
+    if clicked(&menu_query) {
+        next_state.set(GameState::Menu);
+    }
+}
+
+fn menu_labels(
+    config: Res<BoardConfig>,
+    mode: Res<GameMode>,
+    size_buttons: Query<&Children, With<BoardSizeButton>>,
+    mode_buttons: Query<&Children, With<GameModeButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    let labels = [
+        (
+            size_buttons.get_single(),
+            format!("Board: {}x{}", config.width, config.height),
+        ),
+        (mode_buttons.get_single(), format!("Mode: {}", mode.label())),
+    ];
+
+    for (children, label) in labels {
+        let Some(&child) = children.ok().and_then(|children| children.first()) else {
+            continue;
+        };
+        if let Ok(mut text) = texts.get_mut(child) {
+            text.sections[0].value = label;
+        }
+    }
+}
+
+/// Buttons of the menu overlay, top to bottom.
+fn menu_buttons(
+    menus: &Query<&Children, With<MenuOverlay>>,
+    buttons: &Query<(), With<Button>>,
+) -> Vec<Entity> {
+    menus
+        .iter()
+        .flat_map(|children| children.iter().copied())
+        .filter(|child| buttons.contains(*child))
+        .collect()
+}
+
+/// Arrow keys move the selection, Enter clicks the selected button. The click
+/// goes through `Interaction` so the mouse handlers pick it up as is.
+fn navigate_menu(
+    input: Res<Input<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut selection: ResMut<MenuSelection>,
+    menus: Query<&Children, With<MenuOverlay>>,
+    buttons: Query<(), With<Button>>,
+    mut interactions: Query<&mut Interaction>,
+) {
+    if let Some(pressed) = selection.pressed.take() {
+        if let Ok(mut interaction) = interactions.get_mut(pressed) {
+            *interaction = Interaction::None;
+        }
+    }
+
+    let buttons = menu_buttons(&menus, &buttons);
+    if buttons.is_empty() {
+        return;
+    }
+
+    if input.any_just_pressed(bindings.up.iter().copied()) {
+        selection.index = (selection.index + buttons.len() - 1) % buttons.len();
+    }
+    if input.any_just_pressed(bindings.down.iter().copied()) {
+        selection.index = (selection.index + 1) % buttons.len();
+    }
+
+    if input.any_just_pressed([KeyCode::Return, KeyCode::NumpadEnter]) {
+        let button = buttons[selection.index.min(buttons.len() - 1)];
+        if let Ok(mut interaction) = interactions.get_mut(button) {
+            *interaction = Interaction::Clicked;
+            selection.pressed = Some(button);
+        }
+    }
+}
+
+/// Shows the keyboard selection like a hovered button.
+fn highlight_menu_selection(
+    selection: Res<MenuSelection>,
+    menus: Query<&Children, With<MenuOverlay>>,
+    buttons: Query<(), With<Button>>,
+    mut backgrounds: Query<(&Interaction, &mut BackgroundColor)>,
+) {
+    for (index, button) in menu_buttons(&menus, &buttons).into_iter().enumerate() {
+        let Ok((interaction, mut color)) = backgrounds.get_mut(button) else {
+            continue;
+        };
+
+        *color = match interaction {
+            Interaction::Clicked => colors::button::PRESSED,
+            Interaction::Hovered => colors::button::HOVERED,
+            Interaction::None if index == selection.index => colors::button::HOVERED,
+            Interaction::None => colors::button::NORMAL,
+        }
+        .into();
+    }
+}
+
+fn setup_pause_overlay(mut commands: Commands, font_spec: Res<FontSpec>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: styles::OVERLAY,
+                background_color: BackgroundColor(colors::OVERLAY),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseOverlay,
+            StateScoped(GameState::Paused),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Paused",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Press P to resume",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn setup_quit_overlay(mut commands: Commands, font_spec: Res<FontSpec>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: styles::OVERLAY,
+                background_color: BackgroundColor(colors::OVERLAY),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            QuitOverlay,
+            StateScoped(GameState::ConfirmQuit),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Quit?",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ));
+            parent.spawn(TextBundle::from_section(
+                "Press Y to quit or N to keep playing",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 25.0,
+                    color: Color::WHITE,
+                },
+            ));
+        });
+}
+
+fn setup_settings_overlay(mut commands: Commands, font_spec: Res<FontSpec>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: styles::OVERLAY,
+                background_color: BackgroundColor(colors::OVERLAY),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            SettingsOverlay,
+            StateScoped(GameState::Settings),
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Settings",
+                TextStyle {
+                    font: font_spec.family.clone(),
+                    font_size: 60.0,
+                    color: Color::WHITE,
+                },
+            ));
+            // labels are filled in by settings_labels
+            for button in [
+                SettingButton::Theme,
+                SettingButton::Colorblind,
+                SettingButton::Sound,
+                SettingButton::Volume,
+                SettingButton::Animation,
+                SettingButton::BoardSize,
+                SettingButton::Close,
+            ] {
+                spawn_styled_button(parent, &font_spec, styles::MENU_BUTTON, "", button);
+            }
+        });
+}
+
+/// Changes apply right away. Theme, colorblind mode and audio are saved by
+/// their own plugins on change, the rest is saved here.
+#[allow(clippy::too_many_arguments)]
+fn settings_button_system(
+    buttons: Query<(&Interaction, &SettingButton), Changed<Interaction>>,
+    mut theme: ResMut<colors::Theme>,
+    mut colorblind: ResMut<colors::ColorblindMode>,
+    mut audio: ResMut<AudioSettings>,
+    mut animation: ResMut<AnimationConfig>,
+    mut config: ResMut<BoardConfig>,
+    mut close_events: EventWriter<settings::CloseSettings>,
+) {
+    for (interaction, button) in buttons.iter() {
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            SettingButton::Theme => *theme = theme.next(),
+            SettingButton::Colorblind => colorblind.0 = !colorblind.0,
+            SettingButton::Sound => audio.muted = !audio.muted,
+            SettingButton::Volume => audio.next_volume(),
+            SettingButton::Animation => {
+                animation.next_speed();
+                settings::save(&animation, &config);
+            }
+            SettingButton::BoardSize => {
+                config.next_size();
+                settings::save(&animation, &config);
+            }
+            SettingButton::Close => close_events.send(settings::CloseSettings),
+        }
+    }
+}
+
+fn settings_labels(
+    theme: Res<colors::Theme>,
+    colorblind: Res<colors::ColorblindMode>,
+    audio: Res<AudioSettings>,
+    animation: Res<AnimationConfig>,
+    config: Res<BoardConfig>,
+    buttons: Query<(&SettingButton, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    let on_off = |on: bool| if on { "On" } else { "Off" };
+
+    for (button, children) in buttons.iter() {
+        let label = match button {
+            SettingButton::Theme => format!("Theme: {:?}", *theme),
+            SettingButton::Colorblind => format!("Colorblind: {}", on_off(colorblind.0)),
+            SettingButton::Sound => format!("Sound: {}", on_off(!audio.muted)),
+            SettingButton::Volume => format!("Volume: {:.0}%", audio.volume * 100.0),
+            SettingButton::Animation if animation.slide_ms == 0 => "Slides: Off".to_string(),
+            SettingButton::Animation => format!("Slides: {}ms", animation.slide_ms),
+            SettingButton::BoardSize => {
+                format!("Next board: {}x{}", config.width, config.height)
+            }
+            SettingButton::Close => "Close".to_string(),
+        };
+
+        let Some(&child) = children.first() else {
+            continue;
+        };
+        if let Ok(mut text) = texts.get_mut(child) {
+            if text.sections[0].value != label {
+                text.sections[0].value = label;
+            }
+        }
+    }
+}
+
+/// Each frame closes a share of the gap to the score, so merges landing
+/// while it counts just move the target and the count carries on without a
+/// jump. A lower score, after an undo or a new game, shows right away.
 fn scoreboard(
     game: Res<Game>,
-    mut query_score: Query<&mut Text, (With<ScoreDisplay>, Without<BestScoreDisplay>)>,
+    time: Res<Time>,
+    mut query_score: Query<(&mut Text, &mut ScoreDisplay), Without<BestScoreDisplay>>,
     mut query_best_score: Query<&mut Text, (With<BestScoreDisplay>, Without<ScoreDisplay>)>,
 ) {
-    let mut score_text = query_score.single_mut();
+    let (mut score_text, mut display) = query_score.single_mut();
     let mut best_score_text = query_best_score.single_mut();
-    score_text.sections[0].value = game.score.to_string();
+
+    display.step(game.score, time.delta_seconds());
+
+    score_text.sections[0].value = (display.shown.round() as u32).to_string();
     best_score_text.sections[0].value = game.best_score.to_string();
 }
 
-fn button_interaction_system(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
-    >,
-    game_state: ResMut<State<GameState>>,
-    mut next_state: ResMut<NextState<GameState>>,
+fn max_tile_display(game: Res<Game>, mut query_max_tile: Query<&mut Text, With<MaxTileDisplay>>) {
+    let mut max_tile_text = query_max_tile.single_mut();
+    max_tile_text.sections[0].value = game.max_tile.to_string();
+}
+
+fn combo_display(game: Res<Game>, mut query_combo: Query<&mut Text, With<ComboDisplay>>) {
+    let mut combo_text = query_combo.single_mut();
+    combo_text.sections[0].value = format!("x{}", game.combo_multiplier());
+}
+
+fn move_history_display(
+    history: Res<MoveHistory>,
+    mut query_history: Query<&mut Text, With<MoveHistoryDisplay>>,
+) {
+    if !history.is_changed() {
+        return;
+    }
+
+    let lines = history
+        .0
+        .iter()
+        .rev()
+        .map(|(direction, points)| {
+            let arrow = match direction {
+                BoardShift::Left => '←',
+                BoardShift::Right => '→',
+                BoardShift::Up => '↑',
+                BoardShift::Down => '↓',
+            };
+            format!("{arrow} +{points}")
+        })
+        .collect::<Vec<_>>();
+    query_history.single_mut().sections[0].value = lines.join("\n");
+}
+
+fn moves_display(game: Res<Game>, mut query_moves: Query<&mut Text, With<MovesDisplay>>) {
+    let mut moves_text = query_moves.single_mut();
+    moves_text.sections[0].value = game.move_count.to_string();
+}
+
+fn undo_display(budget: Res<UndoBudget>, mut query_undos: Query<&mut Text, With<UndoDisplay>>) {
+    if !budget.is_changed() {
+        return;
+    }
+
+    let mut undos_text = query_undos.single_mut();
+    undos_text.sections[0].value = budget
+        .remaining
+        .map_or("∞".to_string(), |remaining| remaining.to_string());
+}
+
+fn shuffle_display(game: Res<Game>, mut query_shuffles: Query<&mut Text, With<ShuffleDisplay>>) {
+    let mut shuffles_text = query_shuffles.single_mut();
+    shuffles_text.sections[0].value = game.shuffles_left.to_string();
+}
+
+fn removal_display(game: Res<Game>, mut query_removals: Query<&mut Text, With<RemovalDisplay>>) {
+    let mut removals_text = query_removals.single_mut();
+    removals_text.sections[0].value = game.removals_left.to_string();
+}
+
+fn daily_display(
+    daily: Res<DailyChallenge>,
+    mut query_daily: Query<&mut Text, With<DailyDisplay>>,
+) {
+    if !daily.is_changed() {
+        return;
+    }
+
+    let mut daily_text = query_daily.single_mut();
+    daily_text.sections[0].value = match daily.date {
+        Some(date) => format!("Daily {} - seed {}", date, date.seed()),
+        None => String::new(),
+    };
+}
+
+/// Counts down in time attack, up otherwise.
+fn timer_display(
+    timer: Res<GameTimer>,
+    mode: Res<GameMode>,
+    mut query_timer: Query<&mut Text, With<TimerDisplay>>,
 ) {
+    let seconds = match *mode {
+        GameMode::TimeAttack => TIME_ATTACK_LIMIT.saturating_sub(timer.elapsed).as_secs(),
+        GameMode::Classic
+        | GameMode::Endless
+        | GameMode::Daily
+        | GameMode::Zen
+        | GameMode::Practice => timer.elapsed.as_secs(),
+    };
+    let mut timer_text = query_timer.single_mut();
+    timer_text.sections[0].value = format!("{:02}:{:02}", seconds / 60, seconds % 60);
+}
+
+/// Buttons whose interaction changed this frame, with their background.
+type ChangedButtons<'w, 's> = Query<
+    'w,
+    's,
+    (&'static Interaction, &'static mut BackgroundColor),
+    (Changed<Interaction>, With<Button>),
+>;
+
+fn button_color_system(mut interaction_query: ChangedButtons) {
     for (interaction, mut color) in interaction_query.iter_mut() {
         match interaction {
             Interaction::Clicked => {
                 *color = colors::button::PRESSED.into();
-
-                match game_state.0 {
-                    GameState::Playing => {
-                        next_state.set(GameState::GameOver);
-                    }
-                    GameState::GameOver => {
-                        next_state.set(GameState::Playing);
-                    }
-                }
             }
             Interaction::Hovered => {
                 *color = colors::button::HOVERED.into();
@@ -191,8 +952,57 @@ fn button_interaction_system(
     }
 }
 
+fn button_interaction_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<GameButton>)>,
+    game_state: ResMut<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    game: Res<Game>,
+    timer: Res<GameTimer>,
+    mut game_over_events: EventWriter<GameOverEvent>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            match game_state.0 {
+                GameState::Playing
+                | GameState::Paused
+                | GameState::ConfirmQuit
+                | GameState::Settings => {
+                    game_over_events.send(GameOverEvent::new(&game, &timer));
+                    next_state.set(GameState::GameOver);
+                }
+                GameState::Menu | GameState::Won | GameState::GameOver | GameState::Versus => {
+                    next_state.set(GameState::Playing);
+                }
+            }
+        }
+    }
+}
+
+fn restart_button_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<RestartButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction == Interaction::Clicked {
+            // re-entering Playing resets the board, even mid-game
+            next_state.set(GameState::Playing);
+        }
+    }
+}
+
+fn retry_button_system(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<RetryButton>)>,
+    mut retry: ResMut<RetrySeed>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if clicked(&interaction_query) {
+        retry.0 = true;
+        next_state.set(GameState::Playing);
+    }
+}
+
 fn button_text_system(
-    button_query: Query<&Children, With<Button>>,
+    button_query: Query<&Children, With<GameButton>>,
     mut text_query: Query<&mut Text>,
     run_state: Res<State<GameState>>,
 ) {
@@ -203,11 +1013,51 @@ fn button_text_system(
 
     let mut text = text_query.get_mut(*first_child_entity).unwrap();
     match run_state.0 {
-        GameState::Playing => {
+        GameState::Playing | GameState::Paused | GameState::ConfirmQuit | GameState::Settings => {
             text.sections[0].value = "End Game".to_string();
         }
-        GameState::GameOver => {
+        GameState::Menu | GameState::Won | GameState::GameOver | GameState::Versus => {
             text.sections[0].value = "New Game".to_string();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seconds until the display shows `target`, counting from 0.
+    fn settle_secs(target: u32, hz: f32) -> f32 {
+        let mut display = ScoreDisplay::default();
+        let mut elapsed = 0.0;
+        while display.shown < target as f32 {
+            display.step(target, 1.0 / hz);
+            elapsed += 1.0 / hz;
+            assert!(elapsed < 5.0, "the score never settled at {hz} Hz");
+        }
+        elapsed
+    }
+
+    #[test]
+    fn score_counts_at_the_same_pace_at_any_frame_rate() {
+        for target in [4, 256, 20_000] {
+            let at_60 = settle_secs(target, 60.0);
+            for hz in [30.0, 240.0] {
+                let secs = settle_secs(target, hz);
+                // the same up to the frame it lands on
+                assert!(
+                    (secs - at_60).abs() <= 1.0 / 30.0 + f32::EPSILON,
+                    "{target} took {secs}s at {hz} Hz and {at_60}s at 60 Hz"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn lower_score_shows_at_once() {
+        let mut display = ScoreDisplay::default();
+        display.step(100, 1.0);
+        display.step(20, 1.0 / 240.0);
+        assert_eq!(display.shown, 20.0);
+    }
+}
@@ -11,3 +11,49 @@ pub const SCORE_CONTAINER: Style = Style {
     },
     ..Style::DEFAULT
 };
+
+pub const BUTTON: Style = Style {
+    size: Size {
+        width: Val::Px(130.0),
+        height: Val::Px(50.0),
+    },
+    justify_content: JustifyContent::Center,
+    align_items: AlignItems::Center,
+    ..Style::DEFAULT
+};
+
+pub const MENU_BUTTON: Style = Style {
+    size: Size {
+        width: Val::Px(260.0),
+        height: Val::Px(50.0),
+    },
+    ..BUTTON
+};
+
+/// Below the score boxes, on the left of the board.
+pub const MOVE_HISTORY: Style = Style {
+    position_type: PositionType::Absolute,
+    position: UiRect {
+        left: Val::Px(50.0),
+        top: Val::Px(220.0),
+        right: Val::Auto,
+        bottom: Val::Auto,
+    },
+    ..Style::DEFAULT
+};
+
+pub const OVERLAY: Style = Style {
+    position_type: PositionType::Absolute,
+    size: Size {
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+    },
+    flex_direction: FlexDirection::Column,
+    align_items: AlignItems::Center,
+    justify_content: JustifyContent::Center,
+    gap: Size {
+        width: Val::Px(0.0),
+        height: Val::Px(20.0),
+    },
+    ..Style::DEFAULT
+};
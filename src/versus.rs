@@ -0,0 +1,436 @@
+use b2048::{has_any_move, plan_shift, BoardShift};
+use bevy::prelude::*;
+use rand::prelude::*;
+use rand_chacha::ChaCha12Rng;
+
+use crate::{
+    build_board,
+    cli::Args,
+    colors::{self, Theme},
+    display::HEADER_HEIGHT,
+    roll_starting_tiles, shift_board, spawn_starting_tiles,
+    state_scoped::StateScoped,
+    AnimationConfig, Board, BoardConfig, BoardGame, BoardId, BumpEvent, FontSpec, Game, GameState,
+    InputLock, Layout, MergeEvent, NewTileEvent, Points, Position, SeedConfig, StartConfig,
+};
+
+/// Local versus on one keyboard, opened from the menu: two boards side by
+/// side, WASD playing the left one and the arrows the right one. The first
+/// to reach the winning tile wins, or the higher score once both are stuck.
+///
+/// Each player sits at a `VersusPlayer` seat, the parent of a regular
+/// `Board` with its own `BoardId` and a `BoardGame` keeping its score and
+/// rng. The tiles slide, spawn and merge through the same systems as in
+/// `Playing`; only the input and the outcome live here.
+pub struct VersusPlugin;
+
+impl Plugin for VersusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Versus>()
+            .add_systems((start_versus, setup_versus).in_schedule(OnEnter(GameState::Versus)))
+            // the regular board makes way for the seats, see `start_versus`
+            .add_system(crate::spawn_board.in_schedule(OnExit(GameState::Versus)))
+            .add_systems(
+                (
+                    decide_versus.before(versus_input),
+                    versus_input,
+                    update_versus_ui.after(versus_input),
+                    leave_versus,
+                )
+                    .in_set(OnUpdate(GameState::Versus)),
+            );
+    }
+}
+
+const PLAYERS: usize = 2;
+
+/// Keys of each player, in the order left, right, up, down.
+const PLAYER_KEYS: [[KeyCode; 4]; PLAYERS] = [
+    [KeyCode::A, KeyCode::D, KeyCode::W, KeyCode::S],
+    [KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down],
+];
+const DIRECTIONS: [BoardShift; 4] = [
+    BoardShift::Left,
+    BoardShift::Right,
+    BoardShift::Up,
+    BoardShift::Down,
+];
+
+/// Space between the two boards.
+const SEAT_GAP: f32 = 60.0;
+
+/// Parent of a player's board, with the `BoardId` and `BoardGame` of it.
+/// Each player waits for their own slides to finish.
+#[derive(Component)]
+struct VersusPlayer {
+    index: usize,
+    lock: InputLock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Won(usize),
+    Draw,
+}
+
+#[derive(Default, Resource)]
+struct Versus {
+    outcome: Option<Outcome>,
+}
+
+/// Where a player's board stands, see `outcome`.
+#[derive(Debug, Default, Clone, Copy)]
+struct Standing {
+    score: u32,
+    won: bool,
+    stuck: bool,
+}
+
+/// Whoever reached the winning tile wins, the higher score breaking a tie.
+/// Once every board is stuck the higher score wins too. `None` while the
+/// game goes on.
+fn outcome(standings: &[Standing]) -> Option<Outcome> {
+    let standings = standings.iter().enumerate();
+    let contenders = if standings.clone().any(|(_, standing)| standing.won) {
+        standings
+            .filter(|(_, standing)| standing.won)
+            .collect::<Vec<_>>()
+    } else if standings.clone().all(|(_, standing)| standing.stuck) {
+        standings.collect::<Vec<_>>()
+    } else {
+        return None;
+    };
+
+    let best = contenders
+        .iter()
+        .map(|(_, standing)| standing.score)
+        .max()?;
+    let mut leaders = contenders
+        .iter()
+        .filter(|(_, standing)| standing.score == best);
+    match (leaders.next(), leaders.next()) {
+        (Some((player, _)), None) => Some(Outcome::Won(*player)),
+        _ => Some(Outcome::Draw),
+    }
+}
+
+/// Standings in player order, `None` if a player's board is missing.
+fn standings(
+    players: &Query<(&BoardId, &VersusPlayer, &BoardGame)>,
+    boards: &Query<&Board>,
+    tiles: &Query<(&Position, &Points, &BoardId)>,
+    win_target: u32,
+) -> Option<[Standing; PLAYERS]> {
+    let mut standings = [None; PLAYERS];
+
+    for (id, player, board_game) in players.iter() {
+        let board = boards.iter().find(|board| board.id == *id)?;
+        let grid = board.grid(tiles.iter());
+        standings[player.index] = Some(Standing {
+            score: board_game.score,
+            won: grid.has_won(win_target),
+            stuck: !has_any_move(&grid),
+        });
+    }
+
+    let mut filled = [Standing::default(); PLAYERS];
+    for (slot, standing) in filled.iter_mut().zip(standings) {
+        *slot = standing?;
+    }
+    Some(filled)
+}
+
+/// Swaps the regular board for one seat per player. Both players start
+/// from the same seed, so the same moves get the same tiles.
+#[allow(clippy::too_many_arguments)]
+fn start_versus(
+    mut commands: Commands,
+    mut versus: ResMut<Versus>,
+    boards: Query<(Entity, &Board)>,
+    board_config: Res<BoardConfig>,
+    layout: Res<Layout>,
+    start_config: Res<StartConfig>,
+    seed_config: Res<SeedConfig>,
+    args: Res<Args>,
+    mut game: ResMut<Game>,
+    font_spec: Res<FontSpec>,
+    tile_colors: colors::TileColors,
+    theme: Res<Theme>,
+) {
+    let seed = seed_config.seed.unwrap_or_else(rand::random);
+    info!("new versus game with seed {seed}");
+
+    versus.outcome = None;
+    // the rules of a new game, rather than those of a replay watched before
+    (game.four_spawn_chance, game.scale_four_chance) = args.four_chance_rules();
+
+    // it comes back with `spawn_board` on the way out
+    for (entity, board) in boards.iter() {
+        if board.id == BoardId::default() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for index in 0..PLAYERS {
+        let id = BoardId(index as u8 + 1);
+        let board = Board {
+            id,
+            ..Board::new(board_config.width, board_config.height, *layout)
+        };
+        let mut rng = ChaCha12Rng::seed_from_u64(seed);
+        let starting_tiles =
+            roll_starting_tiles(&board, start_config.starting_tiles, &game, &mut rng);
+        let x = (index as f32 - 0.5) * (board.physical_size.x + SEAT_GAP);
+
+        let board_entity = build_board(&mut commands, board.clone(), theme.palette());
+        spawn_starting_tiles(
+            &mut commands,
+            board_entity,
+            &board,
+            &font_spec,
+            &tile_colors,
+            starting_tiles,
+        );
+        commands
+            .spawn((
+                SpatialBundle::from_transform(Transform::from_xyz(x, 0.0, 0.0)),
+                id,
+                BoardGame { score: 0, rng },
+                VersusPlayer {
+                    index,
+                    lock: InputLock::default(),
+                },
+                StateScoped(GameState::Versus),
+            ))
+            .add_child(board_entity);
+    }
+}
+
+#[derive(Component)]
+struct VersusScore(usize);
+
+#[derive(Component)]
+struct VersusStatus;
+
+/// Band over the regular header, with the scores above their boards.
+fn setup_versus(mut commands: Commands, font_spec: Res<FontSpec>, theme: Res<Theme>) {
+    let text_style = |font_size| TextStyle {
+        font: font_spec.family.clone(),
+        font_size,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(0.0),
+                        top: Val::Px(0.0),
+                        ..default()
+                    },
+                    size: Size::new(Val::Percent(100.0), Val::Px(HEADER_HEIGHT)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    gap: Size::all(Val::Px(10.0)),
+                    ..default()
+                },
+                background_color: BackgroundColor(theme.palette().clear),
+                z_index: ZIndex::Global(5),
+                ..default()
+            },
+            StateScoped(GameState::Versus),
+        ))
+        .with_children(|parent| {
+            parent.spawn((TextBundle::from_section("", text_style(40.0)), VersusStatus));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::width(Val::Percent(100.0)),
+                        justify_content: JustifyContent::SpaceAround,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for player in 0..PLAYERS {
+                        parent.spawn((
+                            TextBundle::from_section("", text_style(28.0)),
+                            VersusScore(player),
+                        ));
+                    }
+                });
+
+            parent.spawn(TextBundle::from_section(
+                "WASD  |  Arrows        Space: rematch   Esc: menu",
+                text_style(20.0),
+            ));
+        });
+}
+
+/// Both players may move in the same frame, each shifting only their own
+/// board. Moves are ignored once the game is decided.
+#[allow(clippy::too_many_arguments)]
+fn versus_input(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    versus: Res<Versus>,
+    mut players: Query<(&BoardId, &mut VersusPlayer, &mut BoardGame)>,
+    boards: Query<&Board>,
+    mut tiles: Query<(Entity, &mut Position, &mut Points, &BoardId)>,
+    mut merge_events: EventWriter<MergeEvent>,
+    mut new_tile_events: EventWriter<NewTileEvent>,
+    mut bump_events: EventWriter<BumpEvent>,
+    animation: Res<AnimationConfig>,
+) {
+    for (id, mut player, mut board_game) in players.iter_mut() {
+        player.lock.tick(time.delta());
+        if versus.outcome.is_some() || player.lock.is_locked() {
+            continue;
+        }
+
+        let Some(dir) = PLAYER_KEYS[player.index]
+            .iter()
+            .zip(DIRECTIONS)
+            .find(|(key, _)| input.just_pressed(**key))
+            .map(|(_, dir)| dir)
+        else {
+            continue;
+        };
+        let Some(board) = boards.iter().find(|board| board.id == *id) else {
+            continue;
+        };
+
+        let grid = board.grid(tiles.iter().map(|(_, pos, points, id)| (pos, points, id)));
+        let shift = plan_shift(&grid, dir);
+        if !shift.moved {
+            bump_events.send(BumpEvent { dir, board: *id });
+            continue;
+        }
+
+        shift_board(
+            &mut commands,
+            &mut tiles,
+            &mut merge_events,
+            *id,
+            shift.slides,
+        );
+        board_game.score += shift.score_gain;
+        new_tile_events.send(NewTileEvent(*id));
+        player.lock.lock_for(&animation);
+    }
+}
+
+/// Runs ahead of the input, so the tiles a move merged away and the one it
+/// spawned have been applied to the board by then.
+fn decide_versus(
+    mut versus: ResMut<Versus>,
+    players: Query<(&BoardId, &VersusPlayer, &BoardGame)>,
+    boards: Query<&Board>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    game: Res<Game>,
+) {
+    if versus.outcome.is_some() {
+        return;
+    }
+    let Some(standings) = standings(&players, &boards, &tiles, game.win_target) else {
+        return;
+    };
+
+    versus.outcome = outcome(&standings);
+    if let Some(outcome) = versus.outcome {
+        info!("versus game over: {outcome:?}");
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn update_versus_ui(
+    versus: Res<Versus>,
+    players: Query<(&BoardId, &VersusPlayer, &BoardGame)>,
+    boards: Query<&Board>,
+    tiles: Query<(&Position, &Points, &BoardId)>,
+    game: Res<Game>,
+    mut scores: Query<(&VersusScore, &mut Text), Without<VersusStatus>>,
+    mut statuses: Query<&mut Text, (With<VersusStatus>, Without<VersusScore>)>,
+) {
+    let Some(standings) = standings(&players, &boards, &tiles, game.win_target) else {
+        return;
+    };
+
+    for (score, mut text) in scores.iter_mut() {
+        let standing = standings[score.0];
+        let stuck = if standing.stuck { "  (stuck)" } else { "" };
+        let value = format!("Player {}: {}{stuck}", score.0 + 1, standing.score);
+        // left alone when unchanged, so the text isn't laid out every frame
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+
+    let status = match versus.outcome {
+        None => "Versus".to_string(),
+        Some(Outcome::Won(player)) => format!("Player {} wins!", player + 1),
+        Some(Outcome::Draw) => "Draw!".to_string(),
+    };
+    for mut text in statuses.iter_mut() {
+        if text.sections[0].value != status {
+            text.sections[0].value = status.clone();
+        }
+    }
+}
+
+/// Esc goes back to the menu, Space starts a rematch once the game is
+/// decided.
+fn leave_versus(
+    input: Res<Input<KeyCode>>,
+    versus: Res<Versus>,
+    mut run_state: ResMut<NextState<GameState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        run_state.set(GameState::Menu);
+    } else if versus.outcome.is_some() && input.just_pressed(KeyCode::Space) {
+        // re-entering the state rebuilds everything, see StateScoped
+        run_state.set(GameState::Versus);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn standing(score: u32, won: bool, stuck: bool) -> Standing {
+        Standing { score, won, stuck }
+    }
+
+    #[test]
+    fn the_game_goes_on_while_a_board_can_move() {
+        let standings = [standing(100, false, true), standing(20, false, false)];
+        assert_eq!(outcome(&standings), None);
+    }
+
+    #[test]
+    fn reaching_the_target_wins_whatever_the_scores() {
+        let standings = [standing(5000, false, false), standing(3000, true, false)];
+        assert_eq!(outcome(&standings), Some(Outcome::Won(1)));
+    }
+
+    #[test]
+    fn the_higher_score_wins_once_every_board_is_stuck() {
+        let standings = [standing(300, false, true), standing(200, false, true)];
+        assert_eq!(outcome(&standings), Some(Outcome::Won(0)));
+        let standings = [standing(200, false, true), standing(200, false, true)];
+        assert_eq!(outcome(&standings), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn winning_together_goes_to_the_higher_score() {
+        let standings = [standing(2400, true, false), standing(2600, true, false)];
+        assert_eq!(outcome(&standings), Some(Outcome::Won(1)));
+        let standings = [standing(2400, true, false), standing(2400, true, false)];
+        assert_eq!(outcome(&standings), Some(Outcome::Draw));
+    }
+}